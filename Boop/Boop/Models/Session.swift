@@ -7,6 +7,8 @@ struct Session: Identifiable, Codable, Equatable {
     let pid: Int
     var state: SessionState
     var details: String
+    /// What the permission dialog on screen offers, while awaiting approval
+    var approval: ApprovalRequest?
     let startTime: Date
     var lastUpdateTime: Date
 
@@ -17,6 +19,7 @@ struct Session: Identifiable, Codable, Equatable {
         self.pid = pid
         self.state = .working
         self.details = ""
+        self.approval = nil
         self.startTime = Date()
         self.lastUpdateTime = Date()
     }
@@ -57,6 +60,7 @@ struct Session: Identifiable, Codable, Equatable {
     mutating func updateState(_ newState: SessionState, details: String = "") {
         self.state = newState
         self.details = details
+        self.approval = nil
         self.lastUpdateTime = Date()
     }
 }
@@ -1,8 +1,61 @@
 import Foundation
 
+/// What a permission dialog offers, as boop-pty read it off the screen
+struct ApprovalRequest: Codable, Equatable {
+    let command: String?
+    let choices: [String]?
+    let keys: [String]?
+
+    /// Each answer with what to type for it
+    var answers: [(label: String, keys: String)] {
+        zip(choices ?? [], keys ?? []).map { (label: $0, keys: $1) }
+    }
+}
+
+/// Sent back to a session over the connection it announced itself on
+enum SocketCommand {
+    case respond(sessionId: String, keys: String)
+    case interrupt(sessionId: String)
+
+    private struct JsonCommand: Codable {
+        let type: String
+        let sessionId: String
+        let keys: String?
+
+        enum CodingKeys: String, CodingKey {
+            case type
+            case sessionId = "session_id"
+            case keys
+        }
+    }
+
+    var sessionId: String {
+        switch self {
+        case .respond(let sessionId, _), .interrupt(let sessionId):
+            return sessionId
+        }
+    }
+
+    /// One line of JSON, newline included
+    func serialize() -> String? {
+        let json: JsonCommand
+        switch self {
+        case .respond(let sessionId, let keys):
+            json = JsonCommand(type: "RESPOND", sessionId: sessionId, keys: keys)
+        case .interrupt(let sessionId):
+            json = JsonCommand(type: "INTERRUPT", sessionId: sessionId, keys: nil)
+        }
+        guard let data = try? JSONEncoder().encode(json),
+              let line = String(data: data, encoding: .utf8) else {
+            return nil
+        }
+        return line + "\n"
+    }
+}
+
 enum SocketMessage {
     case start(sessionId: String, tool: String, projectName: String, pid: Int)
-    case state(sessionId: String, state: SessionState, details: String, workingDurationSecs: Int?)
+    case state(sessionId: String, state: SessionState, details: String, workingDurationSecs: Int?, approval: ApprovalRequest?)
     case end(sessionId: String, exitCode: Int)
     case unknown(raw: String)
 
@@ -16,6 +69,7 @@ enum SocketMessage {
         let details: String?
         let exitCode: Int?
         let workingDurationSecs: Int?
+        let approval: ApprovalRequest?
 
         enum CodingKeys: String, CodingKey {
             case type
@@ -27,6 +81,7 @@ enum SocketMessage {
             case details
             case exitCode = "exit_code"
             case workingDurationSecs = "working_duration_secs"
+            case approval
         }
     }
 
@@ -68,7 +123,8 @@ enum SocketMessage {
                 sessionId: json.sessionId,
                 state: state,
                 details: json.details ?? "",
-                workingDurationSecs: json.workingDurationSecs
+                workingDurationSecs: json.workingDurationSecs,
+                approval: json.approval
             )
 
         case "END":
@@ -117,7 +173,8 @@ enum SocketMessage {
                 sessionId: parts[1],
                 state: state,
                 details: details,
-                workingDurationSecs: nil  // Legacy format doesn't support this
+                workingDurationSecs: nil,  // Legacy format doesn't support this
+                approval: nil
             )
 
         case "END":
@@ -111,8 +111,8 @@ final class SessionManager: ObservableObject, SocketServerDelegate {
         case .start(let sessionId, let tool, let projectName, let pid):
             handleSessionStart(sessionId: sessionId, tool: tool, projectName: projectName, pid: pid)
 
-        case .state(let sessionId, let state, let details, let workingDurationSecs):
-            handleSessionStateChange(sessionId: sessionId, state: state, details: details, workingDurationSecs: workingDurationSecs)
+        case .state(let sessionId, let state, let details, let workingDurationSecs, let approval):
+            handleSessionStateChange(sessionId: sessionId, state: state, details: details, workingDurationSecs: workingDurationSecs, approval: approval)
 
         case .end(let sessionId, let exitCode):
             handleSessionEnd(sessionId: sessionId, exitCode: exitCode)
@@ -149,13 +149,14 @@ final class SessionManager: ObservableObject, SocketServerDelegate {
         sessions.insert(session, at: 0)
     }
 
-    private func handleSessionStateChange(sessionId: String, state: SessionState, details: String, workingDurationSecs: Int?) {
+    private func handleSessionStateChange(sessionId: String, state: SessionState, details: String, workingDurationSecs: Int?, approval: ApprovalRequest?) {
         guard let index = sessions.firstIndex(where: { $0.id == sessionId }) else {
             return
         }
 
         let previousState = sessions[index].state
         sessions[index].updateState(state, details: details)
+        sessions[index].approval = approval
 
         // Trigger notification if state changed to something attention-worthy
         if state != previousState {
@@ -224,6 +225,22 @@ final class SessionManager: ObservableObject, SocketServerDelegate {
         }
     }
 
+    // MARK: - Commands
+
+    /// Type one of the answers the session's permission dialog offers
+    func respond(to session: Session, keys: String) {
+        guard session.state == .awaitingApproval,
+              session.approval?.keys?.contains(keys) == true else {
+            return
+        }
+        socketServer?.send(.respond(sessionId: session.id, keys: keys))
+    }
+
+    /// Press Ctrl-C in the session
+    func interrupt(_ session: Session) {
+        socketServer?.send(.interrupt(sessionId: session.id))
+    }
+
     func clearCompletedSessions() {
         sessions.removeAll { $0.state == .completed || $0.state == .error || $0.state == .idle }
     }
@@ -12,7 +12,13 @@ final class SocketServer {
     private var acceptThread: Thread?
     private var isRunning = false
     private let socketPath: URL
-    private let queue = DispatchQueue(label: "com.boop.socketserver")
+    // Concurrent: every boop-pty keeps its connection open for the whole
+    // session, so each needs a reader of its own
+    private let queue = DispatchQueue(label: "com.boop.socketserver", attributes: .concurrent)
+
+    /// Each session's connection, for sending commands back
+    private var clients: [String: Int32] = [:]
+    private let clientsLock = NSLock()
 
     private(set) var isListening: Bool = false {
         didSet {
@@ -122,6 +128,27 @@ final class SocketServer {
         isListening = false
     }
 
+    /// Write a command to the session's connection; false if it isn't connected
+    @discardableResult
+    func send(_ command: SocketCommand) -> Bool {
+        guard let line = command.serialize() else { return false }
+        let bytes = Array(line.utf8)
+
+        clientsLock.lock()
+        defer { clientsLock.unlock() }
+        guard let clientSocket = clients[command.sessionId] else { return false }
+
+        var offset = 0
+        while offset < bytes.count {
+            let written = bytes[offset...].withUnsafeBytes { Darwin.write(clientSocket, $0.baseAddress, $0.count) }
+            if written <= 0 {
+                return false
+            }
+            offset += written
+        }
+        return true
+    }
+
     private func acceptLoop() {
         while isRunning && serverSocket >= 0 {
             var clientAddr = sockaddr_un()
@@ -141,6 +168,10 @@ final class SocketServer {
                 continue
             }
 
+            // A session that went away must not take the app with it
+            var noSigPipe: Int32 = 1
+            setsockopt(clientSocket, SOL_SOCKET, SO_NOSIGPIPE, &noSigPipe, socklen_t(MemoryLayout<Int32>.size))
+
             // Handle client in separate queue
             queue.async { [weak self] in
                 self?.handleClient(socket: clientSocket)
@@ -150,6 +181,10 @@ final class SocketServer {
 
     private func handleClient(socket clientSocket: Int32) {
         defer {
+            // Forget it before closing, so the descriptor can't be reused under us
+            clientsLock.lock()
+            clients = clients.filter { $0.value != clientSocket }
+            clientsLock.unlock()
             Darwin.close(clientSocket)
         }
 
@@ -172,6 +207,11 @@ final class SocketServer {
                     lineBuffer = String(lineBuffer[lineBuffer.index(after: newlineIndex)...])
 
                     if let message = SocketMessage.parse(line) {
+                        if case .start(let sessionId, _, _, _) = message {
+                            clientsLock.lock()
+                            clients[sessionId] = clientSocket
+                            clientsLock.unlock()
+                        }
                         DispatchQueue.main.async {
                             self.delegate?.socketServer(self, didReceiveMessage: message)
                         }
@@ -88,6 +88,7 @@ struct MenuBarView: View {
         VStack(spacing: 0) {
             ForEach(sessionManager.sessions) { session in
                 SessionRowView(session: session)
+                    .contextMenu { sessionMenu(for: session) }
 
                 if session.id != sessionManager.sessions.last?.id {
                     Divider()
@@ -98,6 +99,23 @@ struct MenuBarView: View {
         .padding(.vertical, 4)
     }
 
+    @ViewBuilder
+    private func sessionMenu(for session: Session) -> some View {
+        if session.state == .awaitingApproval, let approval = session.approval {
+            ForEach(Array(approval.answers.enumerated()), id: \.offset) { _, answer in
+                Button(answer.label) {
+                    sessionManager.respond(to: session, keys: answer.keys)
+                }
+            }
+            Divider()
+        }
+        if session.state == .working || session.state == .awaitingApproval || session.state == .idle {
+            Button("Interrupt") {
+                sessionManager.interrupt(session)
+            }
+        }
+    }
+
     private var connectionStatusView: some View {
         Button(action: handleConnectionTap) {
             ConnectionStatusView(
@@ -0,0 +1,163 @@
+use crate::attach::{socket_path, Frame};
+use crate::error::{BoopError, Result};
+use crate::pty::get_terminal_size;
+use signal_hook::consts::signal::SIGWINCH;
+use signal_hook::iterator::Signals;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Ctrl-\ detaches, as in dtach
+pub const DETACH_KEY: u8 = 0x1c;
+
+// What a full-screen tool may have left on: the alternate screen, a hidden
+// cursor, mouse reporting, bracketed paste and application keys
+const RESET_MODES: &[u8] =
+    b"\x1b[?1049l\x1b[?25h\x1b[?1000l\x1b[?1002l\x1b[?1003l\x1b[?1006l\x1b[?2004l\x1b[?1l\x1b>\x1b[0m";
+
+type Writer = Arc<Mutex<UnixStream>>;
+
+/// Connect this terminal to a detached session until the tool exits or the
+/// user presses the detach key. Returns the tool's exit code, or 0 after
+/// detaching.
+pub fn attach(session_id: &str) -> Result<i32> {
+    let stream = connect(session_id)?;
+    let writer: Writer = Arc::new(Mutex::new(stream.try_clone()?));
+
+    // The session takes the size of whoever attached or resized last
+    let (cols, rows) = get_terminal_size().unwrap_or((80, 24));
+    send(&writer, &Frame::Hello { cols, rows })?;
+
+    // Restoration handled globally by signal/panic handlers
+    crate::terminal::set_raw_mode();
+
+    let resize_writer = writer.clone();
+    thread::spawn(move || {
+        if let Ok(mut signals) = Signals::new([SIGWINCH]) {
+            for _ in signals.forever() {
+                if let Ok((cols, rows)) = get_terminal_size() {
+                    if send(&resize_writer, &Frame::Resize { cols, rows }).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let detached = Arc::new(AtomicBool::new(false));
+    let stdin_detached = detached.clone();
+    thread::spawn(move || forward_stdin(writer, stdin_detached));
+
+    let exit_code = show_session(stream, &detached)?;
+    if detached.load(Ordering::Relaxed) {
+        print!("\r\n[detached from {}]\r\n", session_id);
+    }
+    Ok(exit_code.unwrap_or(0))
+}
+
+/// Mirror a session in this terminal, read-only, until it ends or the user
+/// presses q, Ctrl-C or the detach key
+pub fn watch(session_id: &str) -> Result<i32> {
+    let mut stream = connect(session_id)?;
+    Frame::Watch.write_to(&mut stream)?;
+
+    // Keys must not echo over the mirrored screen
+    crate::terminal::set_raw_mode();
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stdin_stopped = stopped.clone();
+    let shutdown = stream.try_clone()?;
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0u8; 64];
+        while let Ok(n) = stdin.read(&mut buf) {
+            if n == 0 {
+                return;
+            }
+            if buf[..n].iter().any(|&b| matches!(b, b'q' | 0x03 | DETACH_KEY)) {
+                stdin_stopped.store(true, Ordering::Relaxed);
+                let _ = shutdown.shutdown(Shutdown::Both);
+                return;
+            }
+        }
+    });
+
+    match show_session(stream, &stopped)? {
+        Some(code) => print!("\r\n[{} exited with code {}]\r\n", session_id, code),
+        None => print!("\r\n[stopped watching {}]\r\n", session_id),
+    }
+    Ok(0)
+}
+
+fn connect(session_id: &str) -> Result<UnixStream> {
+    UnixStream::connect(socket_path(session_id))
+        .map_err(|e| BoopError::Ipc(format!("No running session '{}': {}", session_id, e)))
+}
+
+/// Write the session's output to stdout, starting with a redraw of its
+/// current screen. Returns the tool's exit code, or `None` once `left` is
+/// set and the socket has been shut down under us.
+fn show_session(mut reader: UnixStream, left: &AtomicBool) -> Result<Option<i32>> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut shown = false;
+    let result = loop {
+        match Frame::read_from(&mut reader) {
+            Ok(Some(Frame::Output(data))) => {
+                shown = true;
+                if stdout.write_all(&data).is_err() {
+                    break Ok(None);
+                }
+                let _ = stdout.flush();
+            }
+            Ok(Some(Frame::Exit(code))) => break Ok(Some(code)),
+            Ok(Some(_)) => {}
+            _ if left.load(Ordering::Relaxed) => break Ok(None),
+            // Every session starts with a redraw, unless it turned us away
+            Ok(None) if !shown => {
+                break Err(BoopError::Ipc(
+                    "Session can only be watched; attach is for sessions started with run --detached"
+                        .to_string(),
+                ))
+            }
+            Ok(None) => break Err(BoopError::Ipc("Session closed the connection".to_string())),
+            Err(e) => break Err(BoopError::Ipc(format!("Lost the session: {}", e))),
+        }
+    };
+
+    let _ = stdout.write_all(RESET_MODES);
+    let _ = stdout.flush();
+    result
+}
+
+/// Send keys to the session until the detach key
+fn forward_stdin(writer: Writer, detached: Arc<AtomicBool>) {
+    let mut stdin = io::stdin().lock();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = match stdin.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        let keys = &buf[..n];
+
+        if let Some(at) = keys.iter().position(|&b| b == DETACH_KEY) {
+            if at > 0 {
+                let _ = send(&writer, &Frame::Input(keys[..at].to_vec()));
+            }
+            detached.store(true, Ordering::Relaxed);
+            let _ = writer.lock().unwrap().shutdown(Shutdown::Both);
+            return;
+        }
+        if send(&writer, &Frame::Input(keys.to_vec())).is_err() {
+            return;
+        }
+    }
+}
+
+fn send(writer: &Writer, frame: &Frame) -> io::Result<()> {
+    frame.write_to(&mut *writer.lock().unwrap())
+}
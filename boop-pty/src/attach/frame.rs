@@ -0,0 +1,137 @@
+use std::io::{self, Read, Write};
+
+// Far more than one PTY read, small enough that a bad length can't
+// exhaust memory
+const MAX_PAYLOAD: usize = 1 << 20;
+
+const HELLO: u8 = 1;
+const INPUT: u8 = 2;
+const RESIZE: u8 = 3;
+const OUTPUT: u8 = 4;
+const EXIT: u8 = 5;
+const WATCH: u8 = 6;
+
+/// A message on a session socket: a type byte, the payload length as a
+/// big-endian u32, then the payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// First frame from a client that attaches, with its terminal size
+    Hello { cols: u16, rows: u16 },
+    /// First frame from a client that only watches
+    Watch,
+    /// Keys typed in the client's terminal
+    Input(Vec<u8>),
+    /// The client's terminal was resized; from the session, the size it
+    /// now has
+    Resize { cols: u16, rows: u16 },
+    /// Bytes for the client to write to its terminal
+    Output(Vec<u8>),
+    /// The tool exited with this code; the last frame from the session
+    Exit(i32),
+}
+
+impl Frame {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let (kind, payload) = match self {
+            Frame::Hello { cols, rows } => (HELLO, size_payload(*cols, *rows)),
+            Frame::Watch => (WATCH, Vec::new()),
+            Frame::Input(data) => (INPUT, data.clone()),
+            Frame::Resize { cols, rows } => (RESIZE, size_payload(*cols, *rows)),
+            Frame::Output(data) => (OUTPUT, data.clone()),
+            Frame::Exit(code) => (EXIT, code.to_be_bytes().to_vec()),
+        };
+
+        let mut out = Vec::with_capacity(5 + payload.len());
+        out.push(kind);
+        out.extend((payload.len() as u32).to_be_bytes());
+        out.extend(payload);
+        writer.write_all(&out)?;
+        writer.flush()
+    }
+
+    /// The next frame, or `None` if the other end closed the connection
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut header = [0u8; 5];
+        match reader.read_exact(&mut header) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+
+        let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        if len > MAX_PAYLOAD {
+            return Err(invalid(format!("frame of {} bytes is too large", len)));
+        }
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload)?;
+
+        let frame = match header[0] {
+            HELLO => {
+                let (cols, rows) = parse_size(&payload)?;
+                Frame::Hello { cols, rows }
+            }
+            WATCH => Frame::Watch,
+            INPUT => Frame::Input(payload),
+            RESIZE => {
+                let (cols, rows) = parse_size(&payload)?;
+                Frame::Resize { cols, rows }
+            }
+            OUTPUT => Frame::Output(payload),
+            EXIT => {
+                let code: [u8; 4] = payload
+                    .try_into()
+                    .map_err(|_| invalid("exit frame needs 4 bytes".to_string()))?;
+                Frame::Exit(i32::from_be_bytes(code))
+            }
+            other => return Err(invalid(format!("unknown frame type {}", other))),
+        };
+        Ok(Some(frame))
+    }
+}
+
+fn size_payload(cols: u16, rows: u16) -> Vec<u8> {
+    let mut payload = cols.to_be_bytes().to_vec();
+    payload.extend(rows.to_be_bytes());
+    payload
+}
+
+fn parse_size(payload: &[u8]) -> io::Result<(u16, u16)> {
+    match payload {
+        [c0, c1, r0, r1] => Ok((u16::from_be_bytes([*c0, *c1]), u16::from_be_bytes([*r0, *r1]))),
+        _ => Err(invalid("size frame needs 4 bytes".to_string())),
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let frames = [
+            Frame::Hello { cols: 120, rows: 40 },
+            Frame::Watch,
+            Frame::Input(b"y\r".to_vec()),
+            Frame::Resize { cols: 80, rows: 24 },
+            Frame::Output(b"\x1b[2J> ".to_vec()),
+            Frame::Exit(-1),
+        ];
+        let mut wire = Vec::new();
+        for frame in &frames {
+            frame.write_to(&mut wire).unwrap();
+        }
+
+        let mut reader = wire.as_slice();
+        for frame in &frames {
+            assert_eq!(Frame::read_from(&mut reader).unwrap().as_ref(), Some(frame));
+        }
+        assert_eq!(Frame::read_from(&mut reader).unwrap(), None);
+
+        // Unknown types and oversized lengths are errors, not allocations
+        assert!(Frame::read_from(&mut [9u8, 0, 0, 0, 0].as_slice()).is_err());
+        assert!(Frame::read_from(&mut [4u8, 0xff, 0xff, 0xff, 0xff].as_slice()).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! Every session listens on a socket of its own, which other terminals can
+//! `watch` read-only. `run --detached` keeps the tool under a background
+//! `boop-pty serve` with no terminal; `attach` connects one to it.
+
+mod client;
+mod frame;
+
+pub use client::{attach, watch, DETACH_KEY};
+pub use frame::Frame;
+
+use std::path::PathBuf;
+
+/// `~/.boop/sessions`, where sessions keep their sockets and detached ones
+/// their logs
+pub fn sessions_dir() -> PathBuf {
+    crate::paths::boop_dir().join("sessions")
+}
+
+pub fn socket_path(session_id: &str) -> PathBuf {
+    sessions_dir().join(format!("{}.sock", file_name(session_id)))
+}
+
+/// Where a detached session's stderr goes, since it has no terminal
+pub fn log_path(session_id: &str) -> PathBuf {
+    sessions_dir().join(format!("{}.log", file_name(session_id)))
+}
+
+// Session ids are UUIDs, but never let one escape the directory
+fn file_name(session_id: &str) -> String {
+    session_id.replace(['/', '\\'], "_")
+}
@@ -0,0 +1,296 @@
+use crate::audit::Entry;
+use crate::error::{BoopError, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Every line ends with the hash of everything before it
+const HASH_FIELD: &str = ",\"hash\":\"";
+
+// How much of the newest file to read at first when looking for its last
+// entry; doubled until the entry fits
+const TAIL_BYTES: u64 = 4096;
+
+/// An entry as written: chained to the entry before it
+#[derive(Serialize)]
+struct Chained<'a> {
+    #[serde(flatten)]
+    entry: &'a Entry,
+    /// Hash of the previous entry; empty for the very first
+    prev: &'a str,
+}
+
+/// What `verify` checked
+#[derive(Debug, PartialEq, Eq)]
+pub struct Verified {
+    pub files: usize,
+    pub entries: usize,
+    /// Hash of the newest entry; empty if there are none
+    pub head: String,
+}
+
+/// Append `entry` to today's file in `~/.boop/audit`
+pub fn append(entry: &Entry) -> io::Result<()> {
+    append_to(&crate::audit::audit_dir(), entry)
+}
+
+/// Append `entry` to today's file in `dir`, chained to the last entry any
+/// session wrote there
+pub fn append_to(dir: &Path, entry: &Entry) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    // Sessions append concurrently; the chain needs them one at a time
+    let lock = private_file().write(true).open(dir.join(".lock"))?;
+    if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let prev = match log_files(dir)?.last() {
+        Some(path) => last_hash(path)?,
+        None => String::new(),
+    };
+    let body = serde_json::to_string(&Chained { entry, prev: &prev }).map_err(io::Error::other)?;
+    let hash = hex_digest(body.as_bytes());
+    let line = format!("{}{}{}\"}}\n", &body[..body.len() - 1], HASH_FIELD, hash);
+
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let path = dir.join(format!("{}.jsonl", utc_date(secs)));
+    private_file().append(true).open(path)?.write_all(line.as_bytes())
+    // The lock is released when `lock` is closed
+}
+
+/// Options creating a file only the user can read: the log records what
+/// was run and answered
+fn private_file() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true).mode(0o600);
+    options
+}
+
+/// Check every entry in `dir`, oldest first: each must hash to what it
+/// says, and name the entry before it. With `anchor`, the hash of a head
+/// noted down earlier, that entry must still be there, so removing the
+/// newest entries shows too.
+pub fn verify(dir: &Path, anchor: Option<&str>) -> Result<Verified> {
+    let files = log_files(dir)?;
+    let mut verified = Verified {
+        files: files.len(),
+        entries: 0,
+        head: String::new(),
+    };
+    let mut prev = String::new();
+    let mut anchored = anchor.is_none();
+
+    for path in &files {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let contents = fs::read_to_string(path)?;
+        for (number, line) in contents.lines().enumerate() {
+            let broken = |problem: &str| BoopError::Audit(format!("{} line {}: {}", name, number + 1, problem));
+
+            let (body, hash) = split_line(line).ok_or_else(|| broken("not an audit entry"))?;
+            if hex_digest(body.as_bytes()) != hash {
+                return Err(broken("entry was changed after it was written"));
+            }
+            let fields: serde_json::Value = serde_json::from_str(&body).map_err(|_| broken("not an audit entry"))?;
+            if fields["prev"].as_str() != Some(prev.as_str()) {
+                return Err(broken("an entry before this one is missing or was changed"));
+            }
+
+            prev = hash.to_string();
+            anchored |= anchor == Some(hash);
+            verified.entries += 1;
+        }
+    }
+
+    if !anchored {
+        return Err(BoopError::Audit(format!(
+            "entry {} is missing: entries were removed from the end",
+            anchor.unwrap_or_default()
+        )));
+    }
+    verified.head = prev;
+    Ok(verified)
+}
+
+/// The log's files, oldest first
+fn log_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    // Dates sort by name
+    files.sort();
+    Ok(files)
+}
+
+/// Hash of the last entry in `path`, read from the end of the file. A
+/// line that doesn't parse is hashed whole, so the next entry still follows
+/// it and `verify` reports it.
+fn last_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut window = TAIL_BYTES;
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start))?;
+        let mut tail = Vec::new();
+        (&mut file).take(len - start).read_to_end(&mut tail)?;
+
+        let end = tail.iter().rposition(|&b| b != b'\n').map_or(0, |i| i + 1);
+        let line = match tail[..end].iter().rposition(|&b| b == b'\n') {
+            Some(newline) => &tail[newline + 1..end],
+            // The whole file is one line
+            None if start == 0 => &tail[..end],
+            None => {
+                window *= 2;
+                continue;
+            }
+        };
+        if line.is_empty() {
+            return Ok(String::new());
+        }
+        let line = std::str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        return Ok(match split_line(line) {
+            Some((_, hash)) => hash.to_string(),
+            None => hex_digest(line.as_bytes()),
+        });
+    }
+}
+
+/// Lowercase hex SHA-256 of `data`
+fn hex_digest(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A line's entry as it was hashed, and its hash
+fn split_line(line: &str) -> Option<(String, &str)> {
+    let (head, tail) = line.rsplit_once(HASH_FIELD)?;
+    let hash = tail.strip_suffix("\"}")?;
+    (hash.len() == 64).then(|| (format!("{}}}", head), hash))
+}
+
+/// "2026-10-17" for seconds since the epoch
+fn utc_date(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// "2026-10-17T21:02:38Z" for seconds since the epoch
+pub(crate) fn utc_time(secs: u64) -> String {
+    let time = secs % 86_400;
+    format!("{}T{:02}:{:02}:{:02}Z", utc_date(secs), time / 3600, time / 60 % 60, time % 60)
+}
+
+// Howard Hinnant's days-to-civil algorithm for the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{Approval, Source};
+    use std::os::unix::fs::PermissionsExt;
+
+    fn entry(keys: &[u8]) -> Entry {
+        let mut approval = Approval::start("Do you want to proceed?\n❯ 1. Yes\n  2. No", None);
+        approval.input(Source::Terminal, keys);
+        approval.finish("abc", "boop", "claude", "WORKING")
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boop-audit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_chain_verifies() {
+        let dir = temp_dir("chain");
+        let empty = verify(&dir, None).unwrap();
+        assert_eq!((empty.files, empty.entries, empty.head.as_str()), (0, 0, ""));
+
+        for keys in [b"1", b"2", b"1"] {
+            append_to(&dir, &entry(keys)).unwrap();
+        }
+        let verified = verify(&dir, None).unwrap();
+        assert_eq!((verified.files, verified.entries), (1, 3));
+        assert_eq!(verified.head, last_hash(&log_files(&dir).unwrap()[0]).unwrap());
+
+        // Entries written on an earlier day chain into today's
+        let today = log_files(&dir).unwrap().remove(0);
+        fs::rename(&today, dir.join("2000-01-01.jsonl")).unwrap();
+        append_to(&dir, &entry(b"2")).unwrap();
+        let later = verify(&dir, Some(&verified.head)).unwrap();
+        assert_eq!((later.files, later.entries), (2, 4));
+        assert_ne!(later.head, verified.head);
+
+        // An entry longer than the first read from the end still chains
+        append_to(&dir, &entry(&[b'y'; 3 * TAIL_BYTES as usize])).unwrap();
+        append_to(&dir, &entry(b"1")).unwrap();
+        assert_eq!(verify(&dir, None).unwrap().entries, 6);
+
+        for name in [".lock", &log_files(&dir).unwrap()[1].file_name().unwrap().to_string_lossy()] {
+            let mode = fs::metadata(dir.join(name)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "{}", name);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let dir = temp_dir("tamper");
+        for keys in [b"1", b"2", b"1"] {
+            append_to(&dir, &entry(keys)).unwrap();
+        }
+        let head = verify(&dir, None).unwrap().head;
+        let path = log_files(&dir).unwrap().remove(0);
+        let original = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        // An edited answer
+        fs::write(&path, original.replacen("\"keys\":\"2\"", "\"keys\":\"1\"", 1)).unwrap();
+        let err = verify(&dir, None).unwrap_err().to_string();
+        assert!(err.contains("line 2: entry was changed"), "{}", err);
+
+        // A removed entry
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let err = verify(&dir, None).unwrap_err().to_string();
+        assert!(err.contains("line 2: an entry before this one is missing"), "{}", err);
+
+        // The newest entry removed: only the noted head shows it
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[1])).unwrap();
+        assert_eq!(verify(&dir, None).unwrap().entries, 2);
+        let err = verify(&dir, Some(&head)).unwrap_err().to_string();
+        assert!(err.contains("removed from the end"), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_utc_time() {
+        assert_eq!(utc_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_time(951_827_696), "2000-02-29T12:34:56Z");
+        assert_eq!(utc_date(1_792_271_266), "2026-10-17");
+    }
+}
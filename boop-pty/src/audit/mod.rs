@@ -0,0 +1,136 @@
+//! A record of every permission dialog a session showed and what was typed
+//! in answer, appended to `~/.boop/audit/<date>.jsonl`. Each entry carries
+//! the hash of the one before it, so editing or removing an entry breaks
+//! the chain that `boop-pty audit verify` checks. Removing the newest
+//! entries only shows against a hash of the head noted down earlier.
+
+mod log;
+
+pub use log::{append, append_to, verify, Verified};
+
+use crate::detector::ApprovalRequest;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub fn audit_dir() -> PathBuf {
+    crate::paths::boop_dir().join("audit")
+}
+
+/// Where an answer was typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The terminal running the session
+    Terminal,
+    /// A terminal attached through the session socket
+    Viewer,
+    /// A RESPOND or INTERRUPT from the app, daemon or web UI
+    App,
+    /// A rule in the approval policy
+    Policy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Keystroke {
+    /// Since the dialog was detected
+    pub after_ms: u64,
+    pub source: Source,
+    pub keys: String,
+}
+
+/// One line of the audit log, before it is chained
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    /// When the dialog was detected, UTC
+    pub time: String,
+    pub session_id: String,
+    pub project_name: String,
+    pub tool: String,
+    /// The screen showing the dialog
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<ApprovalRequest>,
+    pub keys: Vec<Keystroke>,
+    /// Until the first keystroke; `None` if nothing was typed
+    pub latency_ms: Option<u64>,
+    /// The state the session went on to, or END if it exited
+    pub outcome: String,
+}
+
+/// A dialog on screen, collecting what is typed until the session moves on
+pub struct Approval {
+    detected: Instant,
+    time: SystemTime,
+    prompt: String,
+    request: Option<ApprovalRequest>,
+    keys: Vec<Keystroke>,
+}
+
+impl Approval {
+    pub fn start(screen_text: &str, request: Option<ApprovalRequest>) -> Self {
+        let lines: Vec<&str> = screen_text.lines().map(str::trim_end).collect();
+        let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+        let last = lines.iter().rposition(|line| !line.is_empty()).map_or(first, |i| i + 1);
+        Self {
+            detected: Instant::now(),
+            time: SystemTime::now(),
+            prompt: lines[first..last].join("\n"),
+            request,
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn input(&mut self, source: Source, keys: &[u8]) {
+        self.keys.push(Keystroke {
+            after_ms: self.detected.elapsed().as_millis() as u64,
+            source,
+            keys: String::from_utf8_lossy(keys).into_owned(),
+        });
+    }
+
+    /// Whether anything was typed since the dialog appeared
+    pub fn is_answered(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn finish(self, session_id: &str, project_name: &str, tool: &str, outcome: &str) -> Entry {
+        let secs = self.time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Entry {
+            time: log::utc_time(secs),
+            session_id: session_id.to_string(),
+            project_name: project_name.to_string(),
+            tool: tool.to_string(),
+            prompt: self.prompt,
+            request: self.request,
+            latency_ms: self.keys.first().map(|keystroke| keystroke.after_ms),
+            keys: self.keys,
+            outcome: outcome.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approval_collects_keys() {
+        let mut approval = Approval::start("\n\nDo you want to proceed?   \n❯ 1. Yes\n\n", None);
+        assert!(!approval.is_answered());
+        approval.input(Source::Terminal, b"1");
+        assert!(approval.is_answered());
+        approval.input(Source::App, b"\x03");
+
+        let entry = approval.finish("abc", "boop", "claude", "WORKING");
+        assert_eq!(entry.prompt, "Do you want to proceed?\n❯ 1. Yes");
+        assert_eq!(entry.keys.len(), 2);
+        assert_eq!(entry.keys[1].keys, "\x03");
+        assert_eq!(entry.latency_ms, Some(entry.keys[0].after_ms));
+        assert!(entry.time.ends_with('Z'));
+
+        let entry = Approval::start("", None).finish("abc", "boop", "claude", "END");
+        assert_eq!(entry.prompt, "");
+        assert_eq!(entry.latency_ms, None);
+    }
+}
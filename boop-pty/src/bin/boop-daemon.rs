@@ -0,0 +1,111 @@
+use boop_pty::daemon::Daemon;
+use boop_pty::notify::{self, LogNotifier, Notifier};
+use boop_pty::error::{BoopError, Result};
+use boop_pty::web::WebServer;
+use signal_hook::consts::signal::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process;
+
+fn print_usage() {
+    eprintln!("Usage: boop-daemon [options]");
+    eprintln!();
+    eprintln!("Listens on the Boop socket and sends notifications for boop-pty");
+    eprintln!("sessions, for machines without the macOS app.");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --socket <path>  Socket to listen on (default: ~/.boop/sock)");
+    eprintln!("  --web <addr>     Also serve the web UI, e.g. on 127.0.0.1:7483; put");
+    eprintln!("                   it behind TLS before exposing it beyond localhost");
+    eprintln!("  -h, --help       Show this help");
+    eprintln!();
+    eprintln!("Environment:");
+    eprintln!("  BOOP_FOCUS_COMMAND  Shell command run when a desktop notification is");
+    eprintln!("                      clicked, with BOOP_SESSION_ID, BOOP_PID, BOOP_TOOL");
+    eprintln!("                      and BOOP_PROJECT set (default: raise the terminal");
+    eprintln!("                      with xdotool)");
+    eprintln!("  BOOP_WEB_TOKEN      Token the web UI asks for (default: a new random one,");
+    eprintln!("                      printed at startup)");
+}
+
+fn run() -> Result<i32> {
+    let mut args = env::args().skip(1);
+    let mut socket_path = boop_pty::paths::boop_dir().join("sock");
+    let mut web_addr = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => {
+                print_usage();
+                return Ok(0);
+            }
+            "--socket" => {
+                let path = args.next().ok_or_else(|| {
+                    BoopError::InvalidArgs("--socket requires a path".to_string())
+                })?;
+                socket_path = PathBuf::from(path);
+            }
+            "--web" => {
+                let addr = args.next().ok_or_else(|| {
+                    BoopError::InvalidArgs("--web requires an address".to_string())
+                })?;
+                web_addr = Some(addr);
+            }
+            other => {
+                print_usage();
+                return Err(BoopError::InvalidArgs(format!("Unknown option: {}", other)));
+            }
+        }
+    }
+
+    let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(LogNotifier)];
+    notifiers.extend(notify::configured());
+    let names: Vec<&str> = notifiers.iter().map(|notifier| notifier.name()).collect();
+    eprintln!("boop-daemon: notifying via {}", names.join(", "));
+    let daemon = Daemon::bind(&socket_path, notifiers)?;
+    eprintln!("boop-daemon: listening on {}", daemon.socket_path().display());
+
+    if let Some(addr) = web_addr {
+        let token = match env::var("BOOP_WEB_TOKEN") {
+            Ok(token) if !token.is_empty() => token,
+            _ => random_token()?,
+        };
+        let web = WebServer::bind(&addr, &token, daemon.registry(), daemon.connections())?;
+        eprintln!("boop-daemon: web UI at http://{}/#token={}", web.addr(), token);
+        std::thread::spawn(move || web.serve());
+    }
+
+    // The socket file is removed on a clean shutdown so boop-pty sees the
+    // server as gone rather than refusing connections
+    let cleanup_path = socket_path.clone();
+    std::thread::spawn(move || {
+        if let Ok(mut signals) = Signals::new([SIGTERM, SIGINT]) {
+            if signals.forever().next().is_some() {
+                let _ = fs::remove_file(&cleanup_path);
+                process::exit(0);
+            }
+        }
+    });
+
+    daemon.serve()?;
+    Ok(0)
+}
+
+fn random_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn main() {
+    match run() {
+        Ok(exit_code) => process::exit(exit_code),
+        Err(e) => {
+            eprintln!("boop-daemon error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::{BoopError, Result};
+use crate::ipc::Command;
+use std::collections::HashMap;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The connection each session's `boop-pty` announced itself on, so
+/// commands can be sent back the way the app sends them
+#[derive(Clone, Default)]
+pub struct Connections {
+    /// Keyed by session id; the number tells connections apart, so a
+    /// closing one doesn't remove the reconnection that replaced it
+    streams: Arc<Mutex<HashMap<String, (u64, UnixStream)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Connections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A number for a new connection, to pass to `insert` and `remove`
+    pub fn connection_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn insert(&self, session_id: &str, connection_id: u64, stream: UnixStream) {
+        self.streams
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), (connection_id, stream));
+    }
+
+    /// Forget the sessions announced on a connection that has closed
+    pub fn remove(&self, connection_id: u64) {
+        self.streams.lock().unwrap().retain(|_, (id, _)| *id != connection_id);
+    }
+
+    /// Type into a session, e.g. to answer its permission dialog
+    pub fn send(&self, command: &Command) -> Result<()> {
+        let mut streams = self.streams.lock().unwrap();
+        let (_, stream) = streams
+            .get_mut(command.session_id())
+            .ok_or_else(|| BoopError::Ipc(format!("session {} is not connected", command.session_id())))?;
+        stream
+            .write_all(command.serialize().as_bytes())
+            .map_err(|e| BoopError::Ipc(format!("Failed to send command: {}", e)))
+    }
+}
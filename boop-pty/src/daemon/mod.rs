@@ -0,0 +1,248 @@
+mod connections;
+mod registry;
+
+pub use connections::Connections;
+pub use registry::{pid_alive, Registry, Session};
+
+use crate::error::{BoopError, Result};
+use crate::ipc::Message;
+use crate::notify::{dismiss, dispatch, Notifier};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// How often sessions whose wrapper died without sending END are cleaned up
+const STALE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Headless socket server: does what the macOS app's `SocketServer` and
+/// `SessionManager` do, for machines without the app
+pub struct Daemon {
+    socket_path: PathBuf,
+    listener: UnixListener,
+    registry: Arc<Mutex<Registry>>,
+    connections: Connections,
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
+}
+
+impl Daemon {
+    /// Listen on `socket_path`, replacing a leftover socket file but not a
+    /// running server
+    pub fn bind(socket_path: &Path, notifiers: Vec<Box<dyn Notifier>>) -> Result<Self> {
+        if let Some(dir) = socket_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        if socket_path.exists() {
+            if UnixStream::connect(socket_path).is_ok() {
+                return Err(BoopError::Ipc(format!(
+                    "another server is already listening on {}",
+                    socket_path.display()
+                )));
+            }
+            fs::remove_file(socket_path)?;
+        }
+
+        let listener = UnixListener::bind(socket_path)?;
+        // Owner read/write only, like the app's socket
+        fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600))?;
+
+        Ok(Self {
+            socket_path: socket_path.to_path_buf(),
+            listener,
+            registry: Arc::new(Mutex::new(Registry::new())),
+            connections: Connections::new(),
+            notifiers: Arc::new(notifiers),
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Shared view of the sessions, for status output and tests
+    pub fn registry(&self) -> Arc<Mutex<Registry>> {
+        self.registry.clone()
+    }
+
+    /// For sending commands to sessions
+    pub fn connections(&self) -> Connections {
+        self.connections.clone()
+    }
+
+    /// Accept connections until the listener fails; each `boop-pty` gets a
+    /// thread of its own
+    pub fn serve(self) -> Result<()> {
+        let registry = self.registry.clone();
+        thread::spawn(move || loop {
+            thread::sleep(STALE_CHECK_INTERVAL);
+            registry.lock().unwrap().remove_stale(pid_alive);
+        });
+
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                // Accept failed, might be temporary
+                Err(_) => {
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+            };
+
+            let registry = self.registry.clone();
+            let connections = self.connections.clone();
+            let notifiers = self.notifiers.clone();
+            thread::spawn(move || Self::handle_client(stream, &registry, &connections, &notifiers));
+        }
+
+        Ok(())
+    }
+
+    fn handle_client(
+        stream: UnixStream,
+        registry: &Mutex<Registry>,
+        connections: &Connections,
+        notifiers: &[Box<dyn Notifier>],
+    ) {
+        let connection_id = connections.connection_id();
+        let writer = stream.try_clone().ok();
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            match Message::parse(&line) {
+                Some(Message::Ack {
+                    session_id,
+                    state,
+                    response_ms,
+                }) => {
+                    // The notification was for a prompt the user is now answering
+                    let answered = registry.lock().unwrap().ack(&session_id, state, response_ms);
+                    if answered {
+                        eprintln!(
+                            "boop-daemon: {} answered after {:.1}s",
+                            session_id,
+                            response_ms as f64 / 1000.0
+                        );
+                        dismiss(notifiers, &session_id);
+                    }
+                }
+                Some(message) => {
+                    if let Message::Start { session_id, .. } = &message {
+                        if let Some(writer) = writer.as_ref().and_then(|writer| writer.try_clone().ok()) {
+                            connections.insert(session_id, connection_id, writer);
+                        }
+                    }
+                    // Deliver outside the lock; a slow notifier must not hold up other sessions
+                    let notification = registry.lock().unwrap().apply(message);
+                    if let Some(notification) = notification {
+                        dispatch(notifiers, &notification);
+                    }
+                }
+                None if !line.trim().is_empty() => eprintln!("boop-daemon: unknown message: {}", line),
+                None => {}
+            }
+        }
+
+        connections.remove(connection_id);
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::SessionState;
+    use crate::ipc::{Command, IpcClient};
+    use crate::notify::{Notification, NotificationKind};
+    use std::time::Instant;
+
+    struct Collect(Arc<Mutex<Vec<Notification>>>, Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Collect {
+        fn name(&self) -> &str {
+            "collect"
+        }
+
+        fn notify(&self, notification: &Notification) -> Result<()> {
+            self.0.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+
+        fn dismiss(&self, session_id: &str) -> Result<()> {
+            self.1.lock().unwrap().push(session_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_daemon_tracks_sessions_and_notifies() {
+        let path = std::env::temp_dir().join(format!("boop-daemon-{}.sock", std::process::id()));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let dismissed = Arc::new(Mutex::new(Vec::new()));
+        let daemon = Daemon::bind(&path, vec![Box::new(Collect(sent.clone(), dismissed.clone()))]).unwrap();
+        let registry = daemon.registry();
+        let connections = daemon.connections();
+
+        // A second server on the same socket is refused
+        assert!(Daemon::bind(&path, Vec::new()).is_err());
+        thread::spawn(move || daemon.serve());
+
+        let mut client = IpcClient::with_socket_path(path.clone());
+        client.send(&Message::start("abc123", "claude", "boop", std::process::id())).unwrap();
+        client.send(&Message::state_with_duration("abc123", SessionState::Idle, "", Some(5))).unwrap();
+        client.send(&Message::state("abc123", SessionState::AwaitingApproval, "Bash: ls")).unwrap();
+        // Only an answer to the prompt still showing clears its notification
+        client.send(&Message::ack("abc123", SessionState::Idle, 900)).unwrap();
+        client.send(&Message::ack("abc123", SessionState::AwaitingApproval, 4200)).unwrap();
+        client.send(&Message::end("abc123", 0)).unwrap();
+
+        wait_for(|| {
+            registry
+                .lock()
+                .unwrap()
+                .get("abc123")
+                .is_some_and(|session| session.state == SessionState::Completed)
+        });
+
+        // Commands go back over the session's own connection
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        let mut answering = IpcClient::with_socket_path(path.clone());
+        answering.on_command(move |command| {
+            let _ = command_tx.send(command);
+        });
+        answering.send(&Message::start("def456", "codex", "boop", std::process::id())).unwrap();
+        wait_for(|| registry.lock().unwrap().get("def456").is_some());
+        let respond = Command::Respond {
+            session_id: "def456".to_string(),
+            keys: "1".to_string(),
+        };
+        connections.send(&respond).unwrap();
+        assert_eq!(command_rx.recv_timeout(Duration::from_secs(5)).unwrap(), respond);
+        assert!(connections.send(&Command::Interrupt { session_id: "nobody".to_string() }).is_err());
+
+        // Idle after 5s of work is too quick; completion right after approval is known
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].kind, NotificationKind::Approval);
+        assert_eq!(sent[0].title, "boop");
+        assert_eq!(*dismissed.lock().unwrap(), ["abc123"]);
+        assert_eq!(registry.lock().unwrap().get("abc123").unwrap().response_ms, Some(4200));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::notify::{Notification, NotificationKind};
+use crate::detector::{ApprovalRequest, SessionState};
+use crate::ipc::Message;
+use crate::tmux::TmuxPane;
+use nix::errno::Errno;
+use nix::sys::signal::kill;
+use nix::unistd::Pid;
+use std::time::{Duration, Instant};
+
+/// Sessions with no message for this long are dropped even if their
+/// process still exists
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A wrapped tool the daemon has heard from
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub id: String,
+    pub tool: String,
+    pub project_name: String,
+    /// The `boop-pty` process wrapping the tool
+    pub pid: u32,
+    pub state: SessionState,
+    pub details: String,
+    /// What the permission dialog on screen asks, while awaiting approval
+    pub approval: Option<ApprovalRequest>,
+    pub tmux: Option<TmuxPane>,
+    /// How long the user took to answer the last approval or idle prompt
+    pub response_ms: Option<u64>,
+    /// Held back because the user was looking at the session; sent if they
+    /// look away before answering
+    pub suppressed: Option<Notification>,
+    pub started: Instant,
+    pub last_update: Instant,
+}
+
+/// Every known session, newest first
+#[derive(Default)]
+pub struct Registry {
+    sessions: Vec<Session>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<&Session> {
+        self.sessions.iter().find(|session| session.id == session_id)
+    }
+
+    pub fn start(&mut self, session_id: &str, tool: &str, project_name: &str, pid: u32, tmux: Option<TmuxPane>) {
+        let now = Instant::now();
+        // A restarted wrapper re-announces itself; keep its current state
+        let state = self
+            .get(session_id)
+            .filter(|session| session.pid == pid)
+            .map_or(SessionState::Working, |session| session.state);
+
+        self.sessions.retain(|session| session.id != session_id);
+        self.sessions.insert(
+            0,
+            Session {
+                id: session_id.to_string(),
+                tool: tool.to_string(),
+                project_name: project_name.to_string(),
+                pid,
+                state,
+                details: String::new(),
+                approval: None,
+                tmux,
+                response_ms: None,
+                suppressed: None,
+                started: now,
+                last_update: now,
+            },
+        );
+    }
+
+    /// Record a state change; returns the notification it calls for, if any.
+    /// Unknown sessions are ignored.
+    pub fn update(
+        &mut self,
+        session_id: &str,
+        state: SessionState,
+        details: &str,
+        working_duration_secs: Option<u64>,
+    ) -> Option<Notification> {
+        let session = self.sessions.iter_mut().find(|session| session.id == session_id)?;
+        let previous = session.state;
+        session.state = state;
+        session.details = details.to_string();
+        session.approval = None;
+        session.last_update = Instant::now();
+        if previous != state {
+            session.suppressed = None;
+        }
+
+        Notification::for_change(previous, state, working_duration_secs).map(|kind| Notification {
+            state,
+            details: details.to_string(),
+            working_duration_secs,
+            pid: Some(session.pid),
+            tmux_target: session.tmux.as_ref().map(TmuxPane::target),
+            ..Notification::new(kind, &session.id, &session.tool, &session.project_name)
+        })
+    }
+
+    /// The wrapped tool exited
+    pub fn end(&mut self, session_id: &str, exit_code: i32) -> Option<Notification> {
+        let state = if exit_code == 0 {
+            SessionState::Completed
+        } else {
+            SessionState::Error
+        };
+        self.update(session_id, state, &format!("Exit code: {}", exit_code), None)
+            .map(|notification| Notification {
+                exit_code: Some(exit_code),
+                ..notification
+            })
+    }
+
+    /// A reminder that an approval is still waiting. Only notifies if the
+    /// session still is waiting, in case the answer crossed it.
+    pub fn escalate(&mut self, session_id: &str, level: u32, waiting_secs: u64) -> Option<Notification> {
+        let session = self.sessions.iter_mut().find(|session| session.id == session_id)?;
+        if session.state != SessionState::AwaitingApproval {
+            return None;
+        }
+        session.last_update = Instant::now();
+
+        let notification = Notification::new(NotificationKind::Approval, &session.id, &session.tool, &session.project_name);
+        Some(Notification {
+            message: format!("{} for {}", notification.message.replacen(" is ", " has been ", 1), waited(waiting_secs)),
+            details: session.details.clone(),
+            pid: Some(session.pid),
+            tmux_target: session.tmux.as_ref().map(TmuxPane::target),
+            escalation: Some(level),
+            ..notification
+        })
+    }
+
+    /// The user started typing into the session; returns whether it was
+    /// still in `state`, so whatever notified them of it can be cleared
+    pub fn ack(&mut self, session_id: &str, state: SessionState, response_ms: u64) -> bool {
+        let Some(session) = self.sessions.iter_mut().find(|session| session.id == session_id) else {
+            return false;
+        };
+        if session.state != state {
+            return false;
+        }
+        session.response_ms = Some(response_ms);
+        session.suppressed = None;
+        session.last_update = Instant::now();
+        true
+    }
+
+    /// Apply a message from `boop-pty`; returns the notification it calls
+    /// for, if any
+    pub fn apply(&mut self, message: Message) -> Option<Notification> {
+        match message {
+            Message::Start {
+                session_id,
+                tool,
+                project_name,
+                pid,
+                tmux,
+            } => {
+                self.start(&session_id, &tool, &project_name, pid, tmux);
+                None
+            }
+            Message::State {
+                session_id,
+                state,
+                details,
+                working_duration_secs,
+                approval,
+                focused,
+                ..
+            } => {
+                let notification = self.update(&session_id, state, &details, working_duration_secs);
+                let session = self.sessions.iter_mut().find(|session| session.id == session_id)?;
+                session.approval = approval;
+                match (notification, focused) {
+                    // The user is looking at the session's terminal already
+                    (Some(notification), Some(true)) => {
+                        session.suppressed = Some(notification);
+                        None
+                    }
+                    (Some(notification), _) => Some(notification),
+                    (None, Some(true)) => None,
+                    // The same state again, now that they have looked away
+                    (None, _) => session.suppressed.take(),
+                }
+            }
+            Message::Escalate {
+                session_id,
+                level,
+                waiting_secs,
+            } => self.escalate(&session_id, level, waiting_secs),
+            // `Daemon` calls `ack` itself, to also clear the notification
+            Message::Ack {
+                session_id,
+                state,
+                response_ms,
+            } => {
+                self.ack(&session_id, state, response_ms);
+                None
+            }
+            Message::End {
+                session_id,
+                exit_code,
+            } => self.end(&session_id, exit_code),
+        }
+    }
+
+    /// Drop sessions whose process has gone away or that have been silent
+    /// for a day; returns the ids removed
+    pub fn remove_stale(&mut self, is_alive: impl Fn(u32) -> bool) -> Vec<String> {
+        let now = Instant::now();
+        let mut removed = Vec::new();
+        self.sessions.retain(|session| {
+            let stale = now.duration_since(session.last_update) > STALE_AFTER || !is_alive(session.pid);
+            if stale {
+                removed.push(session.id.clone());
+            }
+            !stale
+        });
+        removed
+    }
+}
+
+/// "90 seconds", "10 minutes", "2 hours"
+fn waited(secs: u64) -> String {
+    let (amount, unit) = match secs {
+        0..120 => (secs, "second"),
+        120..7200 => (secs / 60, "minute"),
+        _ => (secs / 3600, "hour"),
+    };
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+/// Whether a process with this pid still exists
+pub fn pid_alive(pid: u32) -> bool {
+    // pid 0 would signal our own process group; there is nothing to check
+    if pid == 0 {
+        return true;
+    }
+    // EPERM means it exists but belongs to someone else
+    !matches!(kill(Pid::from_raw(pid as i32), None), Err(Errno::ESRCH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_lifecycle() {
+        let mut registry = Registry::new();
+        registry.start("a", "claude", "boop", 10, None);
+        registry.start("b", "codex", "other", 11, None);
+        assert_eq!(registry.sessions()[0].id, "b");
+
+        let notification = registry
+            .update("a", SessionState::AwaitingApproval, "Bash: ls", Some(3))
+            .unwrap();
+        assert_eq!(notification.kind, NotificationKind::Approval);
+        assert_eq!(notification.message, "Claude is waiting for approval");
+        assert_eq!(notification.details, "Bash: ls");
+        assert_eq!(notification.working_duration_secs, Some(3));
+        assert_eq!(registry.get("a").unwrap().details, "Bash: ls");
+
+        // Answering the approval and exiting doesn't re-notify
+        assert!(registry.update("a", SessionState::Working, "", None).is_none());
+        assert!(registry.update("a", SessionState::AwaitingApproval, "", None).is_some());
+        assert!(registry.end("a", 0).is_none());
+        assert_eq!(registry.get("a").unwrap().state, SessionState::Completed);
+
+        let notification = registry.end("b", 1).unwrap();
+        assert_eq!(notification.kind, NotificationKind::Error);
+        assert_eq!(notification.exit_code, Some(1));
+        assert!(registry.update("unknown", SessionState::Idle, "", None).is_none());
+    }
+
+    #[test]
+    fn test_escalation() {
+        let mut registry = Registry::new();
+        registry.start("a", "claude", "boop", 10, None);
+        assert!(registry.escalate("a", 1, 120).is_none());
+
+        registry.update("a", SessionState::AwaitingApproval, "Bash: ls", None);
+        let notification = registry.apply(Message::escalate("a", 2, 600)).unwrap();
+        assert_eq!(notification.kind, NotificationKind::Approval);
+        assert_eq!(notification.message, "Claude has been waiting for approval for 10 minutes");
+        assert_eq!(notification.details, "Bash: ls");
+        assert_eq!(notification.escalation, Some(2));
+        assert_eq!(waited(7200), "2 hours");
+
+        // Answered in the meantime
+        registry.update("a", SessionState::Working, "", None);
+        assert!(registry.escalate("a", 3, 1800).is_none());
+        assert!(registry.escalate("unknown", 1, 120).is_none());
+    }
+
+    #[test]
+    fn test_focused_terminal_not_notified() {
+        let mut registry = Registry::new();
+        registry.start("a", "claude", "boop", 10, None);
+        let waiting = Message::state("a", SessionState::AwaitingApproval, "Bash: ls");
+        assert!(registry.apply(waiting.clone().with_focus(Some(true))).is_none());
+        assert_eq!(registry.get("a").unwrap().state, SessionState::AwaitingApproval);
+
+        // Looking away before answering brings it back, once
+        let notification = registry.apply(waiting.clone().with_focus(Some(false))).unwrap();
+        assert_eq!(notification.kind, NotificationKind::Approval);
+        assert!(registry.apply(waiting.clone().with_focus(Some(false))).is_none());
+
+        registry.update("a", SessionState::Working, "", None);
+        assert!(registry.apply(waiting.clone().with_focus(Some(false))).is_some());
+        registry.update("a", SessionState::Working, "", None);
+        assert!(registry.apply(waiting.clone()).is_some());
+
+        // Not once it was answered, or the session moved on
+        registry.update("a", SessionState::Working, "", None);
+        assert!(registry.apply(waiting.clone().with_focus(Some(true))).is_none());
+        assert!(registry.ack("a", SessionState::AwaitingApproval, 1500));
+        assert!(registry.apply(waiting.clone().with_focus(Some(false))).is_none());
+        registry.update("a", SessionState::Working, "", None);
+        assert!(registry.apply(waiting.clone().with_focus(Some(true))).is_none());
+        registry.update("a", SessionState::Working, "", None);
+        assert!(registry.apply(waiting.with_focus(Some(false))).is_some());
+    }
+
+    #[test]
+    fn test_ack() {
+        let mut registry = Registry::new();
+        registry.start("a", "claude", "boop", 10, None);
+        registry.update("a", SessionState::AwaitingApproval, "Bash: ls", None);
+
+        // Typed after the session had already moved on
+        assert!(!registry.ack("a", SessionState::Idle, 900));
+        assert!(registry.apply(Message::ack("a", SessionState::AwaitingApproval, 4200)).is_none());
+        assert_eq!(registry.get("a").unwrap().response_ms, Some(4200));
+        assert!(!registry.ack("unknown", SessionState::AwaitingApproval, 100));
+    }
+
+    #[test]
+    fn test_restart_keeps_state() {
+        let mut registry = Registry::new();
+        registry.start("a", "claude", "boop", 10, None);
+        registry.update("a", SessionState::Idle, "", None);
+        registry.start("a", "claude", "boop", 10, None);
+        assert_eq!(registry.get("a").unwrap().state, SessionState::Idle);
+        assert_eq!(registry.sessions().len(), 1);
+
+        // Same id from a different process is a new session
+        registry.start("a", "claude", "boop", 12, None);
+        assert_eq!(registry.get("a").unwrap().state, SessionState::Working);
+    }
+
+    #[test]
+    fn test_remove_stale() {
+        let mut registry = Registry::new();
+        registry.start("alive", "claude", "boop", 10, None);
+        registry.start("dead", "claude", "boop", 11, None);
+        assert_eq!(registry.remove_stale(|pid| pid == 10), vec!["dead".to_string()]);
+        assert_eq!(registry.sessions().len(), 1);
+
+        assert!(pid_alive(std::process::id()));
+    }
+}
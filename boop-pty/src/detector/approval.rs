@@ -0,0 +1,321 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+// How far above the question line to look for the dialog header
+const HEADER_SEARCH_LINES: usize = 12;
+
+// "❯ 1. Yes", "  2. No, and tell Claude what to do differently (esc)"
+static NUMBERED_CHOICE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[❯›>]\s*)?\d+\.\s+(.+)$").unwrap());
+
+// "[Y/n]", "(y/n)", "(Y)es/(N)o"
+static INLINE_CHOICES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\[(]([A-Za-z]+(?:/[A-Za-z]+)+)[\])]").unwrap());
+
+static CLAUDE_QUESTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(do you want to (proceed|make this edit to|create|allow)|claude wants to fetch)").unwrap()
+});
+
+static CLAUDE_EDIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^do you want to make this edit to (.+?)\?").unwrap());
+
+static CLAUDE_CREATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^do you want to create (.+?)\?").unwrap());
+
+static CLAUDE_FETCH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^claude wants to fetch content from (\S+)").unwrap());
+
+static CODEX_QUESTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(would you like to (run the following command|make the following edits)|allow command)").unwrap()
+});
+
+/// What a permission dialog is asking for, as far as it could be read off
+/// the screen
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    /// Normalized tool name: Bash, Edit, Write or WebFetch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Shell command to be run; a command wrapped over several lines of
+    /// the dialog keeps its line breaks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// File to be edited or created, or the URL to be fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Answers offered by the dialog, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+    /// What to type to pick each of `choices`, e.g. "1" in a numbered menu
+    /// or "y\r" at a `[Y/n]` prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+    /// `command` may be cut short or run into the dialog's other text, so
+    /// a policy never approves it
+    #[serde(skip)]
+    pub unclear_command: bool,
+}
+
+impl ApprovalRequest {
+    /// Parse the bottom-most Claude or Codex permission dialog on screen
+    pub fn parse(screen_text: &str) -> Option<Self> {
+        let raw: Vec<&str> = screen_text.lines().collect();
+        let lines: Vec<String> = raw.iter().copied().map(strip_border).collect();
+
+        Self::parse_claude(&raw, &lines)
+            .or_else(|| Self::parse_codex(&lines))
+            .or_else(|| Self::parse_inline(&lines))
+    }
+
+    fn parse_claude(raw: &[&str], lines: &[String]) -> Option<Self> {
+        let question = lines.iter().rposition(|line| CLAUDE_QUESTION.is_match(line))?;
+        let mut request = Self::numbered(&lines[question + 1..]);
+
+        // The dialog header and question name the tool; a Bash command
+        // follows its header
+        let start = question.saturating_sub(HEADER_SEARCH_LINES);
+        for (offset, line) in lines[start..=question].iter().enumerate() {
+            if let Some(caps) = CLAUDE_EDIT.captures(line) {
+                request.tool = Some("Edit".to_string());
+                request.path = Some(caps[1].to_string());
+            } else if let Some(caps) = CLAUDE_CREATE.captures(line) {
+                request.tool = Some("Write".to_string());
+                request.path = Some(caps[1].to_string());
+            } else if let Some(caps) = CLAUDE_FETCH.captures(line) {
+                request.tool = Some("WebFetch".to_string());
+                request.path = Some(caps[1].to_string());
+            } else if line.eq_ignore_ascii_case("bash command") {
+                request.tool = Some("Bash".to_string());
+                let block = start + offset + 1..question;
+                (request.command, request.unclear_command) = bash_command(&raw[block.clone()], &lines[block]);
+            }
+        }
+
+        Some(request)
+    }
+
+    fn parse_codex(lines: &[String]) -> Option<Self> {
+        let question = lines.iter().rposition(|line| CODEX_QUESTION.is_match(line))?;
+        let rest = &lines[question + 1..];
+        let mut request = Self::numbered(rest);
+
+        if lines[question].to_lowercase().contains("edits") {
+            request.tool = Some("Edit".to_string());
+            // File list lines look like "src/lib.rs (+3 -1)"
+            request.path = rest
+                .iter()
+                .find(|line| !line.is_empty() && !NUMBERED_CHOICE.is_match(line))
+                .and_then(|line| line.split_whitespace().next())
+                .map(str::to_string);
+        } else {
+            request.tool = Some("Bash".to_string());
+            // A long command wraps onto the lines after its "$ "
+            if let Some(first) = rest.iter().position(|line| line.starts_with("$ ")) {
+                let command: Vec<&str> = rest[first..]
+                    .iter()
+                    .take_while(|line| !line.is_empty() && !NUMBERED_CHOICE.is_match(line))
+                    .map(|line| line.strip_prefix("$ ").unwrap_or(line).trim())
+                    .collect();
+                request.unclear_command = command.len() > 1;
+                request.command = Some(command.join("\n"));
+            }
+        }
+
+        Some(request)
+    }
+
+    /// Plain `[Y/n]`-style prompts: only the choices are known
+    fn parse_inline(lines: &[String]) -> Option<Self> {
+        let line = lines.iter().rev().find(|line| !line.is_empty())?;
+        let caps = INLINE_CHOICES.captures(line)?;
+        let choices: Vec<String> = caps[1].split('/').map(str::to_string).collect();
+        // "[Y/n]" reads a line; its first letter answers
+        let keys = choices
+            .iter()
+            .map(|choice| format!("{}\r", choice[..1].to_lowercase()))
+            .collect();
+        Some(Self {
+            choices,
+            keys,
+            ..Self::default()
+        })
+    }
+
+    /// A dialog whose numbered choices follow `lines`; pressing a choice's
+    /// number picks it
+    fn numbered(lines: &[String]) -> Self {
+        let choices = numbered_choices(lines);
+        let keys = (1..=choices.len()).map(|n| n.to_string()).collect();
+        Self {
+            choices,
+            keys,
+            ..Self::default()
+        }
+    }
+
+    /// Short description for notification text, e.g. "Bash: git status"
+    pub fn summary(&self) -> Option<String> {
+        let command = self.command.as_ref().map(|command| command.replace('\n', " "));
+        let subject = command.as_ref().or(self.path.as_ref());
+        match (&self.tool, subject) {
+            (Some(tool), Some(subject)) => Some(format!("{}: {}", tool, subject)),
+            (Some(tool), None) => Some(tool.clone()),
+            (None, Some(subject)) => Some(subject.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Drop dialog box borders so only the text inside remains
+fn strip_border(line: &str) -> String {
+    line.trim()
+        .trim_matches(|c| matches!(c, '│' | '┃' | '|'))
+        .trim()
+        .to_string()
+}
+
+/// The command between a Bash dialog's header and its question, and
+/// whether it is unclear. Claude puts a one-line description under it,
+/// unless there is none, and the screen doesn't say which: the last line
+/// is taken as the description unless the one above fills its box and so
+/// may wrap on into it. Only one line, with at most one description under
+/// it, is surely the whole command.
+fn bash_command(raw: &[&str], lines: &[String]) -> (Option<String>, bool) {
+    let block: Vec<(&str, &str)> = raw
+        .iter()
+        .zip(lines)
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(raw, line)| (*raw, line.as_str()))
+        .collect();
+    let described = block.len() > 1 && !fills_box(block[block.len() - 2].0);
+    let command = if described { &block[..block.len() - 1] } else { &block[..] };
+    if command.is_empty() {
+        return (None, false);
+    }
+    let text: Vec<&str> = command.iter().map(|(_, line)| *line).collect();
+    (Some(text.join("\n")), command.len() > 1)
+}
+
+/// Whether a dialog line's text runs up to its right border, as a wrapped
+/// one does; a line without a border might have wrapped at the screen edge
+fn fills_box(raw: &str) -> bool {
+    match raw.trim_end().strip_suffix(['│', '┃', '|']) {
+        Some(inside) => !inside.ends_with("  "),
+        None => true,
+    }
+}
+
+fn numbered_choices(lines: &[String]) -> Vec<String> {
+    lines
+        .iter()
+        .filter_map(|line| NUMBERED_CHOICE.captures(line))
+        .map(|caps| caps[1].trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claude_bash_dialog() {
+        let screen = "\
+╭──────────────────────────────────────────────╮
+│ Bash command                                 │
+│                                              │
+│   git status --short                         │
+│   Show working tree status                   │
+│                                              │
+│ Do you want to proceed?                      │
+│ ❯ 1. Yes                                     │
+│   2. Yes, and don't ask again for git status │
+│   3. No, and tell Claude what to do (esc)    │
+╰──────────────────────────────────────────────╯";
+
+        let request = ApprovalRequest::parse(screen).unwrap();
+        assert_eq!(request.tool.as_deref(), Some("Bash"));
+        assert_eq!(request.command.as_deref(), Some("git status --short"));
+        assert_eq!(request.path, None);
+        assert_eq!(
+            request.choices,
+            vec!["Yes", "Yes, and don't ask again for git status", "No, and tell Claude what to do (esc)"]
+        );
+        assert_eq!(request.summary().as_deref(), Some("Bash: git status --short"));
+        assert!(!request.unclear_command);
+    }
+
+    #[test]
+    fn test_wrapped_bash_command() {
+        let screen = "\
+│ Bash command                                   │
+│                                                │
+│   git status && curl -s https://example.com/in │
+│   stall.sh | sh                                │
+│   Check the tree                               │
+│                                                │
+│ Do you want to proceed?                        │
+│ ❯ 1. Yes                                       │";
+
+        let request = ApprovalRequest::parse(screen).unwrap();
+        assert_eq!(
+            request.command.as_deref(),
+            Some("git status && curl -s https://example.com/in\nstall.sh | sh")
+        );
+        assert_eq!(
+            request.summary().as_deref(),
+            Some("Bash: git status && curl -s https://example.com/in stall.sh | sh")
+        );
+
+        assert!(request.unclear_command);
+
+        let request = ApprovalRequest::parse("Bash command\n  ls\nDo you want to proceed?\n❯ 1. Yes").unwrap();
+        assert_eq!(request.command.as_deref(), Some("ls"));
+        assert!(!request.unclear_command);
+    }
+
+    #[test]
+    fn test_claude_edit_and_write_dialogs() {
+        let request = ApprovalRequest::parse("│ Edit file │\n│ Do you want to make this edit to main.rs? │\n│ ❯ 1. Yes │\n│   2. No │").unwrap();
+        assert_eq!(request.tool.as_deref(), Some("Edit"));
+        assert_eq!(request.path.as_deref(), Some("main.rs"));
+        assert_eq!(request.choices, vec!["Yes", "No"]);
+        assert_eq!(request.keys, vec!["1", "2"]);
+
+        let request = ApprovalRequest::parse("Create file\nDo you want to create notes.md?\n❯ 1. Yes").unwrap();
+        assert_eq!(request.tool.as_deref(), Some("Write"));
+        assert_eq!(request.path.as_deref(), Some("notes.md"));
+    }
+
+    #[test]
+    fn test_claude_fetch_dialog() {
+        let request = ApprovalRequest::parse("Fetch\nClaude wants to fetch content from docs.rs\nDo you want to allow Claude to fetch this content?\n❯ 1. Yes").unwrap();
+        assert_eq!(request.tool.as_deref(), Some("WebFetch"));
+        assert_eq!(request.path.as_deref(), Some("docs.rs"));
+    }
+
+    #[test]
+    fn test_codex_dialogs() {
+        let screen = "Would you like to run the following command?\n\n$ cargo test --workspace\n\n› 1. Yes, proceed\n  2. No, and tell Codex what to do differently";
+        let request = ApprovalRequest::parse(screen).unwrap();
+        assert_eq!(request.tool.as_deref(), Some("Bash"));
+        assert_eq!(request.command.as_deref(), Some("cargo test --workspace"));
+        assert_eq!(request.choices, vec!["Yes, proceed", "No, and tell Codex what to do differently"]);
+
+        let screen = "Would you like to make the following edits?\nsrc/lib.rs (+3 -1)\n› 1. Yes, proceed";
+        let request = ApprovalRequest::parse(screen).unwrap();
+        assert_eq!(request.tool.as_deref(), Some("Edit"));
+        assert_eq!(request.path.as_deref(), Some("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_inline_prompt() {
+        let request = ApprovalRequest::parse("Overwrite config? [Y/n]").unwrap();
+        assert_eq!(request.tool, None);
+        assert_eq!(request.choices, vec!["Y", "n"]);
+        assert_eq!(request.keys, vec!["y\r", "n\r"]);
+        assert_eq!(request.summary(), None);
+
+        assert!(ApprovalRequest::parse("Compiling boop-pty").is_none());
+    }
+}
@@ -1,77 +1,83 @@
+use crate::detector::clock::{Clock, SystemClock};
+use crate::detector::screen::Screen;
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
 use std::time::{Duration, Instant};
-use strip_ansi_escapes::strip;
 
-const MAX_BUFFER_SIZE: usize = 8192;
 const LINE_BUFFER_SIZE: usize = 10;
 const LINE_TTL: Duration = Duration::from_secs(2);
+const SCREEN_WINDOW_ROWS: usize = 12;
+
+// Text left of the cursor that is nothing but a prompt glyph at the start
+// of the line, maybe inside a box or after a mode name: "> ", "│ ❯ ",
+// "architect> ", "$ "
+static INPUT_PROMPT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\s│|]*(\w[\w-]*)?[>›❯»$%#]\s*$").unwrap());
 
 pub struct OutputBuffer {
-    buffer: Vec<u8>,
+    screen: Screen,
     recent_lines: Vec<(String, Instant)>,
+    clock: Arc<dyn Clock>,
 }
 
 impl OutputBuffer {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// Buffer whose line TTL is measured against `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
-            buffer: Vec::with_capacity(MAX_BUFFER_SIZE),
+            screen: Screen::default(),
             recent_lines: Vec::with_capacity(LINE_BUFFER_SIZE),
+            clock,
         }
     }
 
     pub fn append(&mut self, data: &[u8]) {
-        // Strip ANSI escape codes
-        let stripped = strip(data);
-
-        // Add to buffer
-        self.buffer.extend_from_slice(&stripped);
-
-        // Trim buffer if too large (keep last half)
-        if self.buffer.len() > MAX_BUFFER_SIZE {
-            let start = self.buffer.len() - MAX_BUFFER_SIZE / 2;
-            self.buffer = self.buffer[start..].to_vec();
-        }
+        // Render the chunk onto the virtual screen
+        let before = self.screen.lines();
+        self.screen.process(data);
+        let after = self.screen.lines();
+
+        // Rows whose rendered content changed are the "new" output
+        let changed: Vec<String> = after
+            .into_iter()
+            .enumerate()
+            .filter(|(i, line)| !line.trim().is_empty() && before.get(*i) != Some(line))
+            .map(|(_, line)| line.trim().to_string())
+            .collect();
+
+        self.record_lines(changed);
+    }
 
-        // Extract new lines
-        self.extract_lines();
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        self.screen.resize(rows, cols);
     }
 
-    fn extract_lines(&mut self) {
-        let now = Instant::now();
+    fn record_lines(&mut self, lines: Vec<String>) {
+        let now = self.clock.now();
 
         // Remove expired lines first
         self.recent_lines
             .retain(|(_, timestamp)| now.duration_since(*timestamp) < LINE_TTL);
 
-        // Find the position of the last complete line (last newline)
-        if let Some(last_newline) = self.buffer.iter().rposition(|&b| b == b'\n') {
-            // Process all complete lines
-            let complete_text = String::from_utf8_lossy(&self.buffer[..=last_newline]);
-            let mut new_lines = Vec::new();
-            for line in complete_text.lines() {
-                if !line.trim().is_empty() {
-                    new_lines.push(line.to_string());
-                }
-            }
-
-            // Add new lines with current timestamp (don't update existing - only add truly new)
-            for line_str in new_lines.into_iter().rev().take(LINE_BUFFER_SIZE) {
-                if !self.recent_lines.iter().any(|(l, _)| l == &line_str) {
-                    self.recent_lines.insert(0, (line_str, now));
-                }
-            }
-
-            // Keep only the incomplete portion in the buffer (after last newline)
-            self.buffer = self.buffer[last_newline + 1..].to_vec();
-
-            // Trim to max size
-            while self.recent_lines.len() > LINE_BUFFER_SIZE {
-                self.recent_lines.pop();
-            }
+        // Add changed rows in screen order, refreshing any identical older entry
+        for line in lines {
+            self.recent_lines.retain(|(l, _)| l != &line);
+            self.recent_lines.push((line, now));
+        }
+
+        // Trim to max size, dropping the oldest entries
+        if self.recent_lines.len() > LINE_BUFFER_SIZE {
+            let excess = self.recent_lines.len() - LINE_BUFFER_SIZE;
+            self.recent_lines.drain(..excess);
         }
     }
 
+    /// Recently changed rows that are still within the TTL, oldest first
     pub fn get_recent_text(&self) -> String {
-        let now = Instant::now();
+        let now = self.clock.now();
         self.recent_lines
             .iter()
             .filter(|(_, ts)| now.duration_since(*ts) < LINE_TTL)
@@ -81,12 +87,41 @@ impl OutputBuffer {
     }
 
     pub fn get_last_line(&self) -> Option<&str> {
-        let now = Instant::now();
+        let now = self.clock.now();
         self.recent_lines
             .iter()
+            .rev()
             .find(|(_, ts)| now.duration_since(*ts) < LINE_TTL)
             .map(|(line, _)| line.as_str())
     }
+
+    /// Whether the cursor sits right after something that looks like a prompt
+    pub fn looks_like_input(&self) -> bool {
+        INPUT_PROMPT.is_match(&self.screen.cursor_line())
+    }
+
+    /// Window title the program set, empty if none
+    pub fn get_title(&self) -> &str {
+        self.screen.title()
+    }
+
+    /// Escape sequences that redraw the screen, for a newly attached terminal
+    pub fn get_screen_snapshot(&self) -> Vec<u8> {
+        self.screen.snapshot()
+    }
+
+    /// Everything currently visible on screen
+    pub fn get_full_screen_text(&self) -> String {
+        self.screen.text()
+    }
+
+    /// The bottom of what is currently visible on screen, regardless of age
+    pub fn get_screen_text(&self) -> String {
+        let text = self.screen.text();
+        let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+        let start = lines.len().saturating_sub(SCREEN_WINDOW_ROWS);
+        lines[start..].join("\n")
+    }
 }
 
 impl Default for OutputBuffer {
@@ -98,7 +133,7 @@ impl Default for OutputBuffer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
+    use crate::detector::clock::ManualClock;
 
     #[test]
     fn test_buffer_append() {
@@ -125,13 +160,53 @@ mod tests {
     }
 
     #[test]
-    fn test_line_ttl_expiration() {
+    fn test_redraw_replaces_line() {
         let mut buffer = OutputBuffer::new();
+        buffer.append(b"\xe2\xa0\x8b Working\r");
+        buffer.append(b"\x1b[2KDo you want to proceed?\r\n");
+        assert_eq!(buffer.get_last_line(), Some("Do you want to proceed?"));
+        assert!(!buffer.get_screen_text().contains("Working"));
+    }
+
+    #[test]
+    fn test_screen_text() {
+        let mut buffer = OutputBuffer::new();
+        buffer.append(b"output\r\n> ");
+        assert_eq!(buffer.get_screen_text(), "output\n>");
+    }
+
+    #[test]
+    fn test_looks_like_input() {
+        let mut buffer = OutputBuffer::new();
+        buffer.append(b"> ");
+        assert!(buffer.looks_like_input());
+        buffer.append(b"bob");
+        assert!(!buffer.looks_like_input());
+        buffer.append(b"\r\narchitect> ");
+        assert!(buffer.looks_like_input());
+        buffer.append(b"\r\n\xe2\x94\x82 \xe2\x9d\xaf ");
+        assert!(buffer.looks_like_input());
+        buffer.append(b"\r\nCompiling boop-pty");
+        assert!(!buffer.looks_like_input());
+
+        // Output that merely ends in punctuation is not a prompt
+        for line in ["Running tests:", "Continue?", "[INFO] listening on port 80 >", "[1/3]", "done. >"] {
+            buffer.append(format!("\r\n{}", line).as_bytes());
+            assert!(!buffer.looks_like_input(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_line_ttl_expiration() {
+        let clock = ManualClock::new();
+        let mut buffer = OutputBuffer::with_clock(Arc::new(clock.clone()));
         buffer.append(b"Old line\n");
         assert_eq!(buffer.get_last_line(), Some("Old line"));
 
-        // Wait for TTL to expire (2 seconds + margin)
-        thread::sleep(Duration::from_millis(2100));
+        // Still there right up to the TTL
+        clock.advance(LINE_TTL - Duration::from_millis(1));
+        assert_eq!(buffer.get_last_line(), Some("Old line"));
+        clock.advance(Duration::from_millis(1));
 
         // Old line should be expired now
         assert_eq!(buffer.get_last_line(), None);
@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+#[cfg(test)]
+use std::time::Duration;
+
+/// Source of the current time for debounce, silence and line TTL checks
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Wall-clock time, used for live sessions
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Time that only moves when told to. Clones share the same time, so a
+/// replay or test can keep a handle while the detector owns another.
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Move to `to`; time never goes backwards, so earlier instants are ignored
+    pub fn set(&self, to: Instant) {
+        let mut now = self.now.lock().unwrap();
+        *now = (*now).max(to);
+    }
+}
+
+// Tests step time by a duration; replays jump to recorded instants
+#[cfg(test)]
+impl ManualClock {
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let handle = clock.clone();
+        let start = clock.now();
+
+        handle.advance(Duration::from_secs(2));
+        assert_eq!(clock.now(), start + Duration::from_secs(2));
+
+        clock.set(start);
+        assert_eq!(handle.now(), start + Duration::from_secs(2));
+    }
+}
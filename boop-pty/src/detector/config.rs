@@ -0,0 +1,133 @@
+use crate::detector::patterns::{PatternMatcher, RuleConfig};
+use crate::detector::profile::{DetectorProfile, DetectorTiming};
+use crate::error::{BoopError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `~/.boop/patterns.toml`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternsFile {
+    /// Built-in rules to drop, by state
+    #[serde(default)]
+    disable_builtin: Vec<String>,
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+    /// Timing overrides keyed by profile name
+    #[serde(default)]
+    timing: HashMap<String, TimingConfig>,
+}
+
+/// Seconds of silence; 0 turns the check off
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimingConfig {
+    debounce_ms: Option<u64>,
+    idle_silence_secs: Option<u64>,
+    stall_silence_secs: Option<u64>,
+}
+
+/// Everything a `StateDetector` needs: the active rules and timing for one
+/// profile, after applying the user's `~/.boop/patterns.toml`
+pub struct DetectorConfig {
+    pub patterns: PatternMatcher,
+    pub timing: DetectorTiming,
+}
+
+impl DetectorConfig {
+    /// The profile's built-in rules and timing
+    pub fn for_profile(profile: &DetectorProfile) -> Self {
+        Self {
+            patterns: PatternMatcher::for_profile(profile),
+            timing: profile.timing,
+        }
+    }
+
+    /// Profile defaults extended by `~/.boop/patterns.toml`, if it exists
+    pub fn load(profile: &DetectorProfile) -> Result<Self> {
+        let path = Self::config_path();
+        if !path.exists() {
+            return Ok(Self::for_profile(profile));
+        }
+        Self::load_from(profile, &path)
+    }
+
+    pub fn config_path() -> PathBuf {
+        crate::paths::boop_dir().join("patterns.toml")
+    }
+
+    pub fn load_from(profile: &DetectorProfile, path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml(profile, &contents)
+            .map_err(|e| BoopError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn from_toml(profile: &DetectorProfile, contents: &str) -> Result<Self> {
+        let file: PatternsFile =
+            toml::from_str(contents).map_err(|e| BoopError::Config(e.to_string()))?;
+        let patterns = PatternMatcher::with_rules(profile, &file.disable_builtin, &file.rules)?;
+
+        for name in file.timing.keys() {
+            if DetectorProfile::by_name(name).is_none() {
+                return Err(BoopError::Config(format!("timing for unknown profile '{}'", name)));
+            }
+        }
+
+        let mut timing = profile.timing;
+        if let Some(overrides) = file.timing.get(profile.name) {
+            if let Some(ms) = overrides.debounce_ms {
+                timing.debounce = Duration::from_millis(ms);
+            }
+            if let Some(secs) = overrides.idle_silence_secs {
+                timing.idle_silence = silence(secs);
+            }
+            if let Some(secs) = overrides.stall_silence_secs {
+                timing.stall_silence = silence(secs);
+            }
+        }
+
+        Ok(Self { patterns, timing })
+    }
+}
+
+fn silence(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::profile::{AIDER, CLAUDE};
+
+    #[test]
+    fn test_timing_overrides() {
+        let contents = r#"
+            [timing.claude]
+            debounce_ms = 200
+            idle_silence_secs = 0
+            stall_silence_secs = 900
+        "#;
+
+        let config = DetectorConfig::from_toml(&CLAUDE, contents).unwrap();
+        assert_eq!(config.timing.debounce, Duration::from_millis(200));
+        assert_eq!(config.timing.idle_silence, None);
+        assert_eq!(config.timing.stall_silence, Some(Duration::from_secs(900)));
+
+        // Other profiles keep their defaults
+        let config = DetectorConfig::from_toml(&AIDER, contents).unwrap();
+        assert_eq!(config.timing, AIDER.timing);
+
+        // Rules and timing share the file
+        let contents = format!("{}\n[[rules]]\nstate = \"idle\"\npattern = \"zzz\"", contents);
+        let config = DetectorConfig::from_toml(&CLAUDE, &contents).unwrap();
+        assert_eq!(config.timing.debounce, Duration::from_millis(200));
+        assert!(config.patterns.find_match("zzz").is_some());
+
+        let err = DetectorConfig::from_toml(&CLAUDE, "[timing.cluade]\ndebounce_ms = 1")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("unknown profile 'cluade'"));
+    }
+}
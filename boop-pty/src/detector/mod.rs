@@ -1,110 +1,285 @@
+mod approval;
 mod buffer;
+mod clock;
+mod config;
 mod patterns;
+mod profile;
+mod screen;
 mod state;
 
+pub use approval::ApprovalRequest;
 pub use buffer::OutputBuffer;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use config::DetectorConfig;
 pub use patterns::PatternMatcher;
+pub use profile::{DetectorProfile, DetectorTiming};
 pub use state::SessionState;
 
-use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::time::Instant;
 
-const STATE_DEBOUNCE_MS: u64 = 500;
+/// A committed state change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub state: SessionState,
+    /// Time spent working before this change, if the session was working
+    pub working_duration_secs: Option<u64>,
+}
+
+/// Why the detector settled on its current state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// A detection rule matched the screen
+    Rule { name: String, pattern: String },
+    /// Nothing matched, but the tool went quiet at something that looks like a prompt
+    Silence,
+    /// Nothing matched, so the session is assumed to be working
+    NoMatch,
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::Rule { name, pattern } => write!(f, "rule {} /{}/", name, pattern),
+            Trigger::Silence => write!(f, "silence at a prompt"),
+            Trigger::NoMatch => write!(f, "no rule matched"),
+        }
+    }
+}
+
+/// Changes in whether a working session has gone silent for too long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stall {
+    /// No output for `silent_secs` while working
+    Started { silent_secs: u64 },
+    /// Output arrived again while still working
+    Resumed,
+}
 
 pub struct StateDetector {
     current_state: SessionState,
     buffer: OutputBuffer,
+    patterns: PatternMatcher,
+    timing: DetectorTiming,
     last_state_change: Instant,
     pending_state: Option<SessionState>,
     working_started: Option<Instant>,  // Track when work began for notification threshold
+    last_output: Instant,
+    stalled: bool,
+    /// A dialog answered for the user, which may still be on screen
+    answered: Option<ApprovalRequest>,
+    trigger: Option<Trigger>,
+    clock: Arc<dyn Clock>,
 }
 
 impl StateDetector {
     pub fn new() -> Self {
+        Self::with_config(DetectorConfig::for_profile(&profile::GENERIC))
+    }
+
+    /// Detector with the given rules and timing, usually `DetectorConfig::load(profile)`
+    pub fn with_config(config: DetectorConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    /// Detector that measures debounce and silence against `clock`, e.g. a
+    /// `ManualClock` to replay a recording faster than real time
+    pub fn with_clock(config: DetectorConfig, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
         Self {
             current_state: SessionState::Working,
-            buffer: OutputBuffer::new(),
-            last_state_change: Instant::now(),
+            buffer: OutputBuffer::with_clock(clock.clone()),
+            patterns: config.patterns,
+            timing: config.timing,
+            last_state_change: now,
             pending_state: None,
-            working_started: Some(Instant::now()),  // Session starts in working state
+            working_started: Some(now),  // Session starts in working state
+            last_output: now,
+            stalled: false,
+            answered: None,
+            trigger: None,
+            clock,
         }
     }
 
-    /// Returns (new_state, working_duration_secs) if state changed
-    pub fn process_output(&mut self, data: &[u8]) -> Option<(SessionState, Option<u64>)> {
+    /// The permission dialog on screen was answered without the user, e.g.
+    /// by the policy: the session is working again. The dialog itself
+    /// doesn't count again while it lingers on screen, but the next one does.
+    pub fn answered(&mut self) {
+        let now = self.clock.now();
+        self.answered = Some(self.approval_request().unwrap_or_default());
+        self.current_state = SessionState::Working;
+        self.pending_state = None;
+        self.last_state_change = now;
+        self.working_started = Some(now);
+    }
+
+    /// Feed PTY output; returns the transition if a pending state committed
+    pub fn process_output(&mut self, data: &[u8]) -> Option<Transition> {
+        let now = self.clock.now();
         self.buffer.append(data);
+        self.last_output = now;
+        self.evaluate(now)
+    }
 
-        let text = self.buffer.get_recent_text();
-        let detected_state = self.detect_state(&text);
+    /// Re-evaluate without new output, so a pending state commits once its
+    /// debounce period has passed even if the tool has gone quiet
+    pub fn tick(&mut self) -> Option<Transition> {
+        self.evaluate(self.clock.now())
+    }
 
-        // Debounce state changes
-        if detected_state != self.current_state {
-            let now = Instant::now();
-
-            if self.pending_state == Some(detected_state) {
-                // Same pending state, check if debounce period passed
-                if now.duration_since(self.last_state_change) >= Duration::from_millis(STATE_DEBOUNCE_MS) {
-                    let previous_state = self.current_state;
-                    self.current_state = detected_state;
-                    self.pending_state = None;
-                    self.last_state_change = now;
-
-                    // Calculate working duration if transitioning FROM working
-                    let working_duration_secs = if previous_state == SessionState::Working {
-                        self.working_started.map(|start| now.duration_since(start).as_secs())
-                    } else {
-                        None
-                    };
-
-                    // Track working duration transitions
-                    if detected_state == SessionState::Working && previous_state != SessionState::Working {
-                        // Started working
-                        self.working_started = Some(now);
-                    } else if previous_state == SessionState::Working && detected_state != SessionState::Working {
-                        // Stopped working - clear the timer
-                        self.working_started = None;
-                    }
-
-                    return Some((detected_state, working_duration_secs));
-                }
-            } else {
-                // New pending state, start debounce timer
-                self.pending_state = Some(detected_state);
-                self.last_state_change = now;
-            }
+    /// Next moment the outcome of `tick` could change without new output:
+    /// the pending state's commit time, or a silence threshold being reached
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let now = self.clock.now();
+        let pending = self
+            .pending_state
+            .map(|_| self.last_state_change + self.timing.debounce);
+
+        let silence = if self.current_state == SessionState::Working {
+            [self.timing.idle_silence, self.timing.stall_silence]
+                .into_iter()
+                .flatten()
+                .map(|silence| self.last_output + silence)
+                .filter(|deadline| *deadline > now)
+                .min()
         } else {
+            None
+        };
+
+        [pending, silence].into_iter().flatten().min()
+    }
+
+    /// Report the session going silent while working, and output resuming
+    /// afterwards. Each change is returned once.
+    pub fn poll_stall(&mut self) -> Option<Stall> {
+        let silent = self.clock.now().duration_since(self.last_output);
+        let is_stalled = self.current_state == SessionState::Working
+            && self.timing.stall_silence.is_some_and(|threshold| silent >= threshold);
+
+        match (self.stalled, is_stalled) {
+            (false, true) => {
+                self.stalled = true;
+                Some(Stall::Started {
+                    silent_secs: silent.as_secs(),
+                })
+            }
+            (true, false) => {
+                self.stalled = false;
+                // Leaving Working is reported as a transition instead
+                (self.current_state == SessionState::Working).then_some(Stall::Resumed)
+            }
+            _ => None,
+        }
+    }
+
+    fn evaluate(&mut self, now: Instant) -> Option<Transition> {
+        let (mut detected_state, trigger) = self.detect_state(now);
+        if let Some(answered) = self.answered.take() {
+            let lingering = detected_state == SessionState::AwaitingApproval
+                && self.approval_request().unwrap_or_default() == answered;
+            if lingering {
+                detected_state = SessionState::Working;
+                self.answered = Some(answered);
+            }
+        }
+
+        // Debounce state changes
+        if detected_state == self.current_state {
             // State matches current, clear pending
             self.pending_state = None;
+            return None;
         }
 
-        None
-    }
-
-    fn detect_state(&self, text: &str) -> SessionState {
-        if PatternMatcher::is_approval_needed(text) {
-            return SessionState::AwaitingApproval;
+        if self.pending_state != Some(detected_state) {
+            // New pending state, start debounce timer
+            self.pending_state = Some(detected_state);
+            self.last_state_change = now;
         }
 
-        if PatternMatcher::is_error(text) {
-            return SessionState::Error;
+        if now.duration_since(self.last_state_change) < self.timing.debounce {
+            return None;
         }
 
-        if PatternMatcher::is_completed(text) {
-            return SessionState::Completed;
+        let previous_state = self.current_state;
+        self.current_state = detected_state;
+        self.pending_state = None;
+        self.last_state_change = now;
+        self.trigger = Some(trigger);
+
+        // Calculate working duration if transitioning FROM working
+        let working_duration_secs = if previous_state == SessionState::Working {
+            self.working_started.map(|start| now.duration_since(start).as_secs())
+        } else {
+            None
+        };
+
+        // Track working duration transitions
+        if detected_state == SessionState::Working {
+            // Started working
+            self.working_started = Some(now);
+        } else if previous_state == SessionState::Working {
+            // Stopped working - clear the timer
+            self.working_started = None;
         }
 
-        // Check for idle prompt (Claude waiting for input)
-        if PatternMatcher::is_idle_prompt(text) {
-            return SessionState::Idle;
+        Some(Transition {
+            state: detected_state,
+            working_duration_secs,
+        })
+    }
+
+    /// Keep the virtual screen in step with the PTY window size
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        self.buffer.resize(rows, cols);
+    }
+
+    fn detect_state(&self, now: Instant) -> (SessionState, Trigger) {
+        let screen_text = self.buffer.get_screen_text();
+        let recent_text = self.buffer.get_recent_text();
+
+        match self.patterns.detect(&screen_text, &recent_text) {
+            Some(rule) => (
+                rule.state,
+                Trigger::Rule {
+                    name: rule.name.clone(),
+                    pattern: rule.pattern().to_string(),
+                },
+            ),
+            // Nothing matched, but the tool has gone quiet at what looks like
+            // an input prompt: catch prompts the rules don't know about
+            None if self.is_quiescent(now) => (SessionState::Idle, Trigger::Silence),
+            None => (SessionState::Working, Trigger::NoMatch),
         }
+    }
+
+    /// The last committed state
+    pub fn state(&self) -> SessionState {
+        self.current_state
+    }
 
-        SessionState::Working
+    /// A state on screen that is waiting out its debounce period
+    pub fn pending_state(&self) -> Option<SessionState> {
+        self.pending_state
+    }
+
+    /// Why the last committed transition happened; `None` before the first one
+    pub fn trigger(&self) -> Option<&Trigger> {
+        self.trigger.as_ref()
+    }
+
+    fn is_quiescent(&self, now: Instant) -> bool {
+        self.timing
+            .idle_silence
+            .is_some_and(|silence| now.duration_since(self.last_output) >= silence)
+            && self.buffer.looks_like_input()
     }
 
     pub fn get_details(&self) -> String {
         if let Some(line) = self.buffer.get_last_line() {
-            // Truncate if too long
-            if line.len() > 100 {
-                format!("{}...", &line[..97])
+            // Truncate if too long, on a char boundary (box drawing is multi-byte)
+            if line.chars().count() > 100 {
+                format!("{}...", line.chars().take(97).collect::<String>())
             } else {
                 line.to_string()
             }
@@ -112,6 +287,26 @@ impl StateDetector {
             String::new()
         }
     }
+
+    /// Window title the wrapped tool set for itself, empty if none
+    pub fn terminal_title(&self) -> &str {
+        self.buffer.get_title()
+    }
+
+    /// Escape sequences that redraw what the tool has on screen
+    pub fn screen_snapshot(&self) -> Vec<u8> {
+        self.buffer.get_screen_snapshot()
+    }
+
+    /// Everything currently visible on screen, as text
+    pub fn screen_text(&self) -> String {
+        self.buffer.get_full_screen_text()
+    }
+
+    /// What the permission dialog currently on screen is asking for
+    pub fn approval_request(&self) -> Option<ApprovalRequest> {
+        ApprovalRequest::parse(&self.buffer.get_full_screen_text())
+    }
 }
 
 impl Default for StateDetector {
@@ -119,3 +314,178 @@ impl Default for StateDetector {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn manual_detector(timing: DetectorTiming) -> (StateDetector, ManualClock) {
+        let clock = ManualClock::new();
+        let config = DetectorConfig {
+            patterns: PatternMatcher::for_profile(&profile::GENERIC),
+            timing,
+        };
+        (StateDetector::with_clock(config, Arc::new(clock.clone())), clock)
+    }
+
+    fn generic_detector() -> (StateDetector, ManualClock) {
+        manual_detector(profile::GENERIC.timing)
+    }
+
+    fn quick_detector() -> (StateDetector, ManualClock) {
+        manual_detector(DetectorTiming {
+            debounce: Duration::ZERO,
+            idle_silence: Some(Duration::from_millis(100)),
+            stall_silence: Some(Duration::from_millis(200)),
+        })
+    }
+
+    #[test]
+    fn test_pending_state_commits_on_tick() {
+        let (mut detector, clock) = generic_detector();
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(detector.process_output(b"Overwrite file? [Y/n] "), None);
+        let debounce = profile::GENERIC.timing.debounce;
+        assert_eq!(detector.next_deadline(), Some(clock.now() + debounce));
+
+        // No further output: the timer alone commits the prompt
+        assert_eq!(detector.tick(), None);
+        clock.advance(debounce);
+        let transition = detector.tick().unwrap();
+        assert_eq!(transition.state, SessionState::AwaitingApproval);
+        assert_eq!(transition.working_duration_secs, Some(3));
+        assert!(detector.pending_state.is_none());
+        assert_eq!(detector.tick(), None);
+    }
+
+    #[test]
+    fn test_debounce_boundary() {
+        let (mut detector, clock) = generic_detector();
+        detector.process_output(b"Overwrite file? [Y/n] ");
+
+        clock.advance(profile::GENERIC.timing.debounce - Duration::from_millis(1));
+        assert_eq!(detector.tick(), None);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(detector.tick().unwrap().state, SessionState::AwaitingApproval);
+    }
+
+    #[test]
+    fn test_flicker_restarts_debounce() {
+        let (mut detector, clock) = generic_detector();
+        detector.process_output(b"Overwrite file? [Y/n] ");
+        clock.advance(Duration::from_millis(400));
+
+        // A redraw briefly hides the prompt, then it comes back
+        detector.process_output(b"\r\x1b[2Kcompiling...");
+        detector.process_output(b"\r\x1b[2KOverwrite file? [Y/n] ");
+        clock.advance(Duration::from_millis(400));
+        assert_eq!(detector.tick(), None);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(detector.tick().unwrap().state, SessionState::AwaitingApproval);
+    }
+
+    #[test]
+    fn test_pending_state_cleared_when_screen_reverts() {
+        let (mut detector, _clock) = generic_detector();
+        detector.process_output(b"Overwrite file? [Y/n] ");
+        detector.process_output(b"\r\x1b[2Kcompiling...");
+        assert!(detector.pending_state.is_none());
+    }
+
+    #[test]
+    fn test_answered_prompt_scrolls_out_of_window() {
+        let (mut detector, _clock) = generic_detector();
+        detector.process_output(b"Overwrite file? [Y/n] y\r\n");
+        assert!(detector.pending_state.is_some());
+        detector.process_output(b"writing file\r\nchecking output\r\n");
+        assert!(detector.pending_state.is_none());
+    }
+
+    #[test]
+    fn test_answered_dialog_waits_for_the_next() {
+        let (mut detector, clock) = generic_detector();
+        let debounce = profile::GENERIC.timing.debounce;
+        detector.process_output(b"Overwrite file? [Y/n] ");
+        clock.advance(debounce);
+        assert_eq!(detector.tick().unwrap().state, SessionState::AwaitingApproval);
+
+        // Still on screen until the tool redraws
+        detector.answered();
+        clock.advance(debounce);
+        assert_eq!(detector.tick(), None);
+        assert!(detector.pending_state.is_none());
+
+        detector.process_output(b"y\r\nwriting file\r\nchecking output\r\n");
+        detector.process_output(b"Overwrite another? [Y/n] ");
+        clock.advance(debounce);
+        let transition = detector.tick().unwrap();
+        assert_eq!(transition.state, SessionState::AwaitingApproval);
+        assert_eq!(transition.working_duration_secs, Some(1));
+
+        // A different dialog drawn right over the answered one counts too
+        detector.answered();
+        detector.process_output(b"\x1b[2J\x1b[HRun this? (y/n) ");
+        clock.advance(debounce);
+        assert_eq!(detector.tick().unwrap().state, SessionState::AwaitingApproval);
+    }
+
+    #[test]
+    fn test_error_line_expires() {
+        let (mut detector, clock) = generic_detector();
+        detector.process_output(b"error: build failed\r\n");
+        assert!(detector.pending_state.is_some());
+
+        // Past the line TTL the error no longer counts, so nothing commits
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(detector.tick(), None);
+        assert!(detector.pending_state.is_none());
+    }
+
+    #[test]
+    fn test_quiet_prompt_becomes_idle() {
+        let (mut detector, clock) = quick_detector();
+        // No rule knows this prompt
+        assert_eq!(detector.process_output(b"architect> "), None);
+        assert_eq!(detector.next_deadline(), Some(clock.now() + Duration::from_millis(100)));
+
+        clock.advance(Duration::from_millis(99));
+        assert_eq!(detector.tick(), None);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(detector.tick().unwrap().state, SessionState::Idle);
+        assert_eq!(detector.trigger(), Some(&Trigger::Silence));
+
+        // Typing brings it back to working
+        assert_eq!(detector.process_output(b"bob").unwrap().state, SessionState::Working);
+    }
+
+    #[test]
+    fn test_quiet_output_is_not_idle() {
+        let (mut detector, clock) = quick_detector();
+        detector.process_output(b"Compiling boop-pty\r\n");
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(detector.tick(), None);
+
+        // Not even when the last line ends like a question or a label
+        detector.process_output(b"Running tests:");
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(detector.tick(), None);
+    }
+
+    #[test]
+    fn test_stall_reported_once() {
+        let (mut detector, clock) = quick_detector();
+        detector.process_output(b"Compiling boop-pty\r\n");
+        assert_eq!(detector.poll_stall(), None);
+
+        clock.advance(Duration::from_millis(199));
+        assert_eq!(detector.poll_stall(), None);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(detector.poll_stall(), Some(Stall::Started { silent_secs: 0 }));
+        assert_eq!(detector.poll_stall(), None);
+
+        detector.process_output(b"Finished\r\n");
+        assert_eq!(detector.poll_stall(), Some(Stall::Resumed));
+        assert_eq!(detector.poll_stall(), None);
+    }
+}
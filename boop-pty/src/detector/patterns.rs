@@ -1,130 +1,358 @@
+use crate::detector::profile::{DetectorProfile, GENERIC};
+use crate::detector::state::SessionState;
+use crate::error::{BoopError, Result};
 use regex::Regex;
-use std::sync::LazyLock;
-
-static APPROVAL_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        // Claude Code CLI patterns
-        Regex::new(r"(?i)do you want to proceed").unwrap(),
-        Regex::new(r"(?i)allow this action").unwrap(),
-        Regex::new(r"(?i)press enter to continue").unwrap(),
-        Regex::new(r"\[Y/n\]").unwrap(),
-        Regex::new(r"\[y/N\]").unwrap(),
-        Regex::new(r"(?i)waiting for approval").unwrap(),
-        Regex::new(r"(?i)requires? your permission").unwrap(),
-        Regex::new(r"(?i)approve\?").unwrap(),
-        Regex::new(r"(?i)confirm\?").unwrap(),
-        // Additional approval indicators
-        Regex::new(r"(?i)do you want to allow").unwrap(),
-        Regex::new(r"(?i)proceed\s*\?").unwrap(),
-        Regex::new(r"(?i)continue\s*\?").unwrap(),
-        Regex::new(r"(?i)accept\s*\?").unwrap(),
-        Regex::new(r"(?i)yes/no").unwrap(),
-        // Plan mode patterns
-        Regex::new(r"(?i)review.*plan").unwrap(),
-        Regex::new(r"(?i)approve.*plan").unwrap(),
-    ]
-});
-
-static COMPLETION_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        Regex::new(r"(?i)task completed").unwrap(),
-        Regex::new(r"(?i)successfully completed").unwrap(),
-        Regex::new(r"(?i)finished successfully").unwrap(),
-        Regex::new(r"(?i)done\!").unwrap(),
-    ]
-});
-
-// Patterns for detecting when Claude Code is idle and waiting for input
-static IDLE_PROMPT_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        // Claude Code input prompt - line starting with > followed by space or end
-        Regex::new(r"^>\s*$").unwrap(),
-    ]
-});
-
-static ERROR_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        Regex::new(r"(?i)error:").unwrap(),
-        Regex::new(r"(?i)fatal error").unwrap(),
-        Regex::new(r"(?i)failed:").unwrap(),
-        Regex::new(r"(?i)exception:").unwrap(),
-        Regex::new(r"(?i)panic:").unwrap(),
-    ]
-});
-
-pub struct PatternMatcher;
+use serde::Deserialize;
+
+/// Default priority for rules targeting a state; higher wins when several match
+fn default_priority(state: SessionState) -> i32 {
+    match state {
+        SessionState::AwaitingApproval => 400,
+        SessionState::Error => 300,
+        SessionState::Completed => 200,
+        SessionState::Idle => 100,
+        SessionState::Working => 0,
+    }
+}
+
+/// Prompts sit at the bottom of the screen, so by default approval and idle
+/// rules only look as far up as the profile expects the prompt to be
+fn default_last_lines(profile: &DetectorProfile, state: SessionState) -> Option<usize> {
+    match state {
+        SessionState::AwaitingApproval => Some(profile.approval_prompt_lines),
+        SessionState::Idle => Some(profile.idle_prompt_lines),
+        _ => None,
+    }
+}
+
+/// A single detection rule: when `pattern` matches and no `exclude` pattern
+/// does, the session is considered to be in `state`. With `last_lines` set,
+/// only that many non-empty lines from the bottom are checked, one at a time.
+pub struct Rule {
+    pub name: String,
+    pub state: SessionState,
+    pub priority: i32,
+    pattern: Regex,
+    exclude: Vec<Regex>,
+    last_lines: Option<usize>,
+}
+
+impl Rule {
+    pub fn new(
+        name: &str,
+        state: SessionState,
+        pattern: &str,
+        exclude: &[&str],
+        priority: i32,
+        last_lines: Option<usize>,
+    ) -> Result<Self> {
+        let compile = |source: &str| {
+            Regex::new(source).map_err(|e| {
+                BoopError::Config(format!("rule '{}' has an invalid pattern: {}", name, e))
+            })
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            state,
+            priority,
+            pattern: compile(pattern)?,
+            exclude: exclude.iter().map(|p| compile(p)).collect::<Result<_>>()?,
+            last_lines,
+        })
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        let matches = |subject: &str| {
+            self.pattern.is_match(subject) && !self.exclude.iter().any(|p| p.is_match(subject))
+        };
+
+        match self.last_lines {
+            Some(count) => text
+                .lines()
+                .rev()
+                .filter(|line| !line.trim().is_empty())
+                .take(count)
+                .any(matches),
+            None => matches(text),
+        }
+    }
+}
+
+/// A `[[rules]]` entry of `~/.boop/patterns.toml`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    name: Option<String>,
+    state: String,
+    pattern: String,
+    #[serde(default)]
+    exclude: Vec<String>,
+    priority: Option<i32>,
+    last_lines: Option<usize>,
+}
+
+/// Ordered set of detection rules, highest priority first
+pub struct PatternMatcher {
+    rules: Vec<Rule>,
+}
 
 impl PatternMatcher {
-    pub fn is_approval_needed(text: &str) -> bool {
-        APPROVAL_PATTERNS.iter().any(|pattern| pattern.is_match(text))
+    /// The built-in rules of a detector profile
+    pub fn for_profile(profile: &DetectorProfile) -> Self {
+        let mut matcher = Self { rules: Vec::new() };
+        for (state, patterns) in [
+            (SessionState::AwaitingApproval, profile.approval_patterns),
+            (SessionState::Error, profile.error_patterns),
+            (SessionState::Completed, profile.completion_patterns),
+            (SessionState::Idle, profile.idle_prompt_patterns),
+        ] {
+            for (i, pattern) in patterns.iter().enumerate() {
+                let name = format!("{}:{}:{}", profile.name, state.as_str().to_lowercase(), i);
+                let rule = Rule::new(
+                    &name,
+                    state,
+                    pattern,
+                    &[],
+                    default_priority(state),
+                    default_last_lines(profile, state),
+                )
+                .expect("built-in pattern must compile");
+                matcher.rules.push(rule);
+            }
+        }
+        matcher
+    }
+
+    /// Profile rules without the states in `disable_builtin`, extended by
+    /// custom `rules`
+    pub fn with_rules(profile: &DetectorProfile, disable_builtin: &[String], rules: &[RuleConfig]) -> Result<Self> {
+        let disabled = disable_builtin
+            .iter()
+            .map(|s| parse_state(s))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut custom = Vec::with_capacity(rules.len());
+        for (i, config) in rules.iter().enumerate() {
+            let name = config.name.clone().unwrap_or_else(|| format!("rule #{}", i + 1));
+            let state = parse_state(&config.state)
+                .map_err(|e| BoopError::Config(format!("rule '{}': {}", name, e)))?;
+            let exclude: Vec<&str> = config.exclude.iter().map(String::as_str).collect();
+            let priority = config.priority.unwrap_or_else(|| default_priority(state));
+            let last_lines = config.last_lines.or_else(|| default_last_lines(profile, state));
+            custom.push(Rule::new(&name, state, &config.pattern, &exclude, priority, last_lines)?);
+        }
+
+        let mut matcher = Self::for_profile(profile);
+        matcher.rules.retain(|rule| !disabled.contains(&rule.state));
+        matcher.add_rules(custom);
+        Ok(matcher)
     }
 
-    pub fn is_completed(text: &str) -> bool {
-        COMPLETION_PATTERNS.iter().any(|pattern| pattern.is_match(text))
+    /// Add rules ahead of existing ones of equal priority, so they act as overrides
+    pub fn add_rules(&mut self, rules: Vec<Rule>) {
+        let mut combined = rules;
+        combined.append(&mut self.rules);
+        // Stable sort keeps the new rules first among equal priorities
+        combined.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        self.rules = combined;
     }
 
-    pub fn is_error(text: &str) -> bool {
-        ERROR_PATTERNS.iter().any(|pattern| pattern.is_match(text))
+    /// Highest-priority rule matching the current output. Prompts stay on
+    /// screen until answered, so approval, idle and working rules look at the
+    /// visible screen. Errors and completions are one-off lines, so they only
+    /// look at recently changed rows, otherwise stale text would stick.
+    pub fn detect(&self, screen_text: &str, recent_text: &str) -> Option<&Rule> {
+        self.rules.iter().find(|rule| match rule.state {
+            SessionState::Error | SessionState::Completed => rule.is_match(recent_text),
+            _ => rule.is_match(screen_text),
+        })
+    }
+}
+
+// Single-text helpers used by the pattern tests
+#[cfg(test)]
+impl PatternMatcher {
+    /// Highest-priority rule matching the given text
+    pub fn find_match(&self, text: &str) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.is_match(text))
+    }
+
+    fn matches_state(&self, state: SessionState, text: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.state == state && rule.is_match(text))
+    }
+
+    pub fn is_approval_needed(&self, text: &str) -> bool {
+        self.matches_state(SessionState::AwaitingApproval, text)
+    }
+
+    pub fn is_completed(&self, text: &str) -> bool {
+        self.matches_state(SessionState::Completed, text)
+    }
+
+    pub fn is_error(&self, text: &str) -> bool {
+        self.matches_state(SessionState::Error, text)
     }
 
     /// Check if the last non-empty line is the idle prompt (> at start of line)
-    pub fn is_idle_prompt(text: &str) -> bool {
-        text.lines()
-            .rev()
-            .find(|line| !line.trim().is_empty())
-            .map(|line| IDLE_PROMPT_PATTERNS.iter().any(|p| p.is_match(line)))
-            .unwrap_or(false)
+    pub fn is_idle_prompt(&self, text: &str) -> bool {
+        self.matches_state(SessionState::Idle, text)
+    }
+}
+
+impl Default for PatternMatcher {
+    fn default() -> Self {
+        Self::for_profile(&GENERIC)
     }
 }
 
+fn parse_state(name: &str) -> Result<SessionState> {
+    name.parse()
+        .map_err(|_| BoopError::Config(format!("unknown state '{}'", name)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::detector::config::DetectorConfig;
+    use crate::detector::profile::CLAUDE;
+
+    fn from_toml(contents: &str) -> Result<PatternMatcher> {
+        DetectorConfig::from_toml(&CLAUDE, contents).map(|config| config.patterns)
+    }
 
     #[test]
     fn test_approval_patterns() {
-        assert!(PatternMatcher::is_approval_needed("Do you want to proceed?"));
-        assert!(PatternMatcher::is_approval_needed("Allow this action? [Y/n]"));
-        assert!(PatternMatcher::is_approval_needed("This requires your permission"));
-        assert!(PatternMatcher::is_approval_needed("approve?"));
-        assert!(!PatternMatcher::is_approval_needed("Working on task..."));
+        let matcher = PatternMatcher::for_profile(&CLAUDE);
+        assert!(matcher.is_approval_needed("Do you want to proceed?"));
+        assert!(matcher.is_approval_needed("Allow this action? [Y/n]"));
+        assert!(matcher.is_approval_needed("This requires your permission"));
+        assert!(matcher.is_approval_needed("approve?"));
+        assert!(!matcher.is_approval_needed("Working on task..."));
     }
 
     #[test]
     fn test_completion_patterns() {
-        assert!(PatternMatcher::is_completed("Task completed successfully"));
-        assert!(PatternMatcher::is_completed("Done!"));
-        assert!(!PatternMatcher::is_completed("Still working..."));
+        let matcher = PatternMatcher::for_profile(&CLAUDE);
+        assert!(matcher.is_completed("Task completed successfully"));
+        assert!(matcher.is_completed("Done!"));
+        assert!(!matcher.is_completed("Still working..."));
     }
 
     #[test]
     fn test_error_patterns() {
-        assert!(PatternMatcher::is_error("Error: something went wrong"));
-        assert!(PatternMatcher::is_error("Fatal error occurred"));
-        assert!(!PatternMatcher::is_error("Everything is fine"));
+        let matcher = PatternMatcher::for_profile(&CLAUDE);
+        assert!(matcher.is_error("Error: something went wrong"));
+        assert!(matcher.is_error("Fatal error occurred"));
+        assert!(!matcher.is_error("Everything is fine"));
     }
 
     #[test]
     fn test_idle_prompt_patterns() {
+        let matcher = PatternMatcher::for_profile(&CLAUDE);
+
         // Simple prompt
-        assert!(PatternMatcher::is_idle_prompt(">"));
-        assert!(PatternMatcher::is_idle_prompt("> "));
-        assert!(PatternMatcher::is_idle_prompt(">\n"));
+        assert!(matcher.is_idle_prompt(">"));
+        assert!(matcher.is_idle_prompt("> "));
+        assert!(matcher.is_idle_prompt(">\n"));
 
         // Prompt after output
-        assert!(PatternMatcher::is_idle_prompt("Some output\n>"));
-        assert!(PatternMatcher::is_idle_prompt("Some output\n> "));
-        assert!(PatternMatcher::is_idle_prompt("Line 1\nLine 2\n>"));
+        assert!(matcher.is_idle_prompt("Some output\n>"));
+        assert!(matcher.is_idle_prompt("Some output\n> "));
+        assert!(matcher.is_idle_prompt("Line 1\nLine 2\n>"));
 
         // Prompt with trailing whitespace/newlines
-        assert!(PatternMatcher::is_idle_prompt("Output\n>\n"));
-        assert!(PatternMatcher::is_idle_prompt("Output\n> \n\n"));
+        assert!(matcher.is_idle_prompt("Output\n>\n"));
+        assert!(matcher.is_idle_prompt("Output\n> \n\n"));
 
         // Should NOT match
-        assert!(!PatternMatcher::is_idle_prompt("> ls")); // Command being typed
-        assert!(!PatternMatcher::is_idle_prompt(">command")); // No space
-        assert!(!PatternMatcher::is_idle_prompt("still working...")); // No prompt
-        assert!(!PatternMatcher::is_idle_prompt("")); // Empty
-        assert!(!PatternMatcher::is_idle_prompt("  >  ")); // Indented prompt (not at start of line)
+        assert!(!matcher.is_idle_prompt("> ls")); // Command being typed
+        assert!(!matcher.is_idle_prompt(">command")); // No space
+        assert!(!matcher.is_idle_prompt("still working...")); // No prompt
+        assert!(!matcher.is_idle_prompt("")); // Empty
+        assert!(!matcher.is_idle_prompt("  >  ")); // Indented prompt (not at start of line)
+    }
+
+    #[test]
+    fn test_builtin_priority_order() {
+        let matcher = PatternMatcher::for_profile(&CLAUDE);
+        let rule = matcher.find_match("error: build failed\nDo you want to proceed?").unwrap();
+        assert_eq!(rule.state, SessionState::AwaitingApproval);
+    }
+
+    #[test]
+    fn test_custom_rules_extend_builtin() {
+        let matcher = from_toml(
+            r#"
+            [[rules]]
+            name = "codex-approval"
+            state = "awaiting_approval"
+            pattern = "(?i)allow command\\?"
+            exclude = ["(?i)always allow"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matcher.is_approval_needed("Allow command?"));
+        assert!(!matcher.is_approval_needed("Always allow command?"));
+        // Built-ins are still active
+        assert!(matcher.is_approval_needed("Do you want to proceed?"));
+    }
+
+    #[test]
+    fn test_custom_rule_priority_overrides() {
+        let matcher = from_toml(
+            r#"
+            [[rules]]
+            state = "working"
+            pattern = "esc to interrupt"
+            priority = 1000
+            "#,
+        )
+        .unwrap();
+
+        let rule = matcher.find_match("Continue? (esc to interrupt)").unwrap();
+        assert_eq!(rule.state, SessionState::Working);
+    }
+
+    #[test]
+    fn test_disable_builtin() {
+        let matcher = from_toml(r#"disable_builtin = ["ERROR"]"#).unwrap();
+        assert!(!matcher.is_error("error: something"));
+        assert!(matcher.is_approval_needed("[Y/n]"));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let err = from_toml(
+            r#"
+            [[rules]]
+            name = "broken"
+            state = "idle"
+            pattern = "(unclosed"
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("rule 'broken' has an invalid pattern"));
+
+        let err = from_toml(
+            r#"
+            [[rules]]
+            state = "sleeping"
+            pattern = "zzz"
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("unknown state 'sleeping'"));
+
+        assert!(from_toml("[[rules]]\nstate = \"idle\"").is_err());
     }
 }
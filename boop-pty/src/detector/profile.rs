@@ -0,0 +1,263 @@
+use std::path::Path;
+use std::time::Duration;
+
+/// Per-tool detection settings: the built-in pattern sets, where prompts sit
+/// on screen, and how long a new state must hold before it is
+/// reported.
+pub struct DetectorProfile {
+    pub name: &'static str,
+    pub approval_patterns: &'static [&'static str],
+    /// How many non-empty lines from the bottom an approval prompt may appear
+    /// in; answered prompts that have scrolled further up no longer count
+    pub approval_prompt_lines: usize,
+    pub completion_patterns: &'static [&'static str],
+    pub error_patterns: &'static [&'static str],
+    pub idle_prompt_patterns: &'static [&'static str],
+    /// How many non-empty lines from the bottom the idle prompt may appear in
+    pub idle_prompt_lines: usize,
+    pub timing: DetectorTiming,
+}
+
+/// Time-based detection settings; users can override these per profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectorTiming {
+    /// How long a newly detected state must hold before it is reported
+    pub debounce: Duration,
+    /// Silence after which a screen that looks like it wants input counts as
+    /// idle, even if no idle rule matched
+    pub idle_silence: Option<Duration>,
+    /// Silence while working after which the session is reported as stalled
+    pub stall_silence: Option<Duration>,
+}
+
+// Shared by every profile
+const COMMON_ERROR_PATTERNS: &[&str] = &[
+    r"(?i)error:",
+    r"(?i)fatal error",
+    r"(?i)failed:",
+    r"(?i)exception:",
+    r"(?i)panic:",
+];
+
+pub static CLAUDE: DetectorProfile = DetectorProfile {
+    name: "claude",
+    approval_patterns: &[
+        // Claude Code CLI patterns
+        r"(?i)do you want to proceed",
+        r"(?i)allow this action",
+        r"(?i)press enter to continue",
+        r"\[Y/n\]",
+        r"\[y/N\]",
+        r"(?i)waiting for approval",
+        r"(?i)requires? your permission",
+        r"(?i)approve\?",
+        r"(?i)confirm\?",
+        // Additional approval indicators
+        r"(?i)do you want to allow",
+        r"(?i)proceed\s*\?",
+        r"(?i)continue\s*\?",
+        r"(?i)accept\s*\?",
+        r"(?i)yes/no",
+        // Plan mode patterns
+        r"(?i)review.*plan",
+        r"(?i)approve.*plan",
+    ],
+    // The permission dialog box, its options and the footer below it
+    approval_prompt_lines: 8,
+    completion_patterns: &[
+        r"(?i)task completed",
+        r"(?i)successfully completed",
+        r"(?i)finished successfully",
+        r"(?i)done\!",
+    ],
+    error_patterns: COMMON_ERROR_PATTERNS,
+    // Claude Code input prompt - line starting with > followed by space or end
+    idle_prompt_patterns: &[r"^>\s*$"],
+    idle_prompt_lines: 1,
+    timing: DetectorTiming {
+        debounce: Duration::from_millis(500),
+        idle_silence: Some(Duration::from_secs(5)),
+        stall_silence: Some(Duration::from_secs(300)),
+    },
+};
+
+pub static CODEX: DetectorProfile = DetectorProfile {
+    name: "codex",
+    approval_patterns: &[
+        r"(?i)allow command\?",
+        r"(?i)would you like to run the following command",
+        r"(?i)would you like to make the following edits",
+        r"(?i)yes,? (and )?(proceed|allow)",
+        r"(?i)no,? and tell codex",
+        r"\[Y/n\]",
+        r"\[y/N\]",
+    ],
+    approval_prompt_lines: 6,
+    completion_patterns: &[r"(?i)task completed", r"(?i)turn completed"],
+    error_patterns: COMMON_ERROR_PATTERNS,
+    // Codex composer: a bare prompt marker, optionally showing the placeholder
+    idle_prompt_patterns: &[r"^\s*[›▌>]\s*$", r"(?i)^\s*[›▌>]\s*ask codex to do anything"],
+    // The composer is followed by a hint line (e.g. "⏎ send")
+    idle_prompt_lines: 2,
+    timing: DetectorTiming {
+        debounce: Duration::from_millis(500),
+        idle_silence: Some(Duration::from_secs(5)),
+        stall_silence: Some(Duration::from_secs(300)),
+    },
+};
+
+pub static AIDER: DetectorProfile = DetectorProfile {
+    name: "aider",
+    approval_patterns: &[
+        // e.g. "Add file to the chat? (Y)es/(N)o/(A)ll/(S)kip all/(D)on't ask again [Yes]:"
+        r"\(Y\)es/\(N\)o",
+        r"(?i)run shell commands?\?",
+        r"(?i)create new file\?",
+        r"(?i)allow edits to file",
+    ],
+    approval_prompt_lines: 1,
+    completion_patterns: &[r"(?i)^applied edit to", r"(?i)^commit [0-9a-f]{7,}"],
+    error_patterns: COMMON_ERROR_PATTERNS,
+    // Prompt carries the chat mode, e.g. "> ", "architect> ", "ask> "
+    idle_prompt_patterns: &[r"^(\w+)?>\s*$"],
+    idle_prompt_lines: 1,
+    // Aider prints prompts as plain lines, so there is little redraw to wait out
+    // and a quiet prompt settles quickly
+    timing: DetectorTiming {
+        debounce: Duration::from_millis(300),
+        idle_silence: Some(Duration::from_secs(3)),
+        stall_silence: Some(Duration::from_secs(180)),
+    },
+};
+
+pub static GEMINI: DetectorProfile = DetectorProfile {
+    name: "gemini",
+    approval_patterns: &[
+        r"(?i)allow execution",
+        r"(?i)apply this change\?",
+        r"(?i)waiting for user confirmation",
+        r"(?i)yes,? allow (once|always)",
+        r"(?i)do you want to proceed",
+    ],
+    approval_prompt_lines: 8,
+    completion_patterns: &[],
+    error_patterns: COMMON_ERROR_PATTERNS,
+    idle_prompt_patterns: &[r"(?i)^[\s│|]*>\s+type your message", r"^[\s│|]*>\s*[│|]?\s*$"],
+    // The input box is followed by its border and a status footer
+    idle_prompt_lines: 4,
+    // Full-screen repaints are frequent, so give them longer to settle
+    timing: DetectorTiming {
+        debounce: Duration::from_millis(750),
+        idle_silence: Some(Duration::from_secs(5)),
+        stall_silence: Some(Duration::from_secs(300)),
+    },
+};
+
+pub static GENERIC: DetectorProfile = DetectorProfile {
+    name: "generic",
+    approval_patterns: &[
+        r"\[Y/n\]",
+        r"\[y/N\]",
+        r"(?i)\(y/n\)",
+        r"(?i)yes/no",
+        r"(?i)do you want to (proceed|continue|allow)",
+        r"(?i)waiting for approval",
+        r"(?i)requires? your permission",
+        r"(?i)press enter to continue",
+    ],
+    approval_prompt_lines: 2,
+    completion_patterns: &[
+        r"(?i)task completed",
+        r"(?i)successfully completed",
+        r"(?i)finished successfully",
+    ],
+    error_patterns: COMMON_ERROR_PATTERNS,
+    // Common REPL prompts with nothing typed after them
+    idle_prompt_patterns: &[r"^[>❯›$%#]\s*$"],
+    idle_prompt_lines: 1,
+    timing: DetectorTiming {
+        debounce: Duration::from_millis(500),
+        idle_silence: Some(Duration::from_secs(5)),
+        stall_silence: Some(Duration::from_secs(300)),
+    },
+};
+
+static PROFILES: [&DetectorProfile; 5] = [&CLAUDE, &CODEX, &AIDER, &GEMINI, &GENERIC];
+
+impl DetectorProfile {
+    pub fn by_name(name: &str) -> Option<&'static DetectorProfile> {
+        PROFILES
+            .iter()
+            .copied()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Profile for the wrapped command, e.g. "claude" or "/opt/bin/codex",
+    /// falling back to the generic profile for unknown tools
+    pub fn for_tool(tool: &str) -> &'static DetectorProfile {
+        let name = Path::new(tool)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::by_name(&name).unwrap_or(&GENERIC)
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        PROFILES.iter().map(|profile| profile.name).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::{PatternMatcher, SessionState};
+
+    fn detect(profile: &DetectorProfile, screen: &str) -> Option<SessionState> {
+        PatternMatcher::for_profile(profile)
+            .detect(screen, "")
+            .map(|rule| rule.state)
+    }
+
+    #[test]
+    fn test_profile_for_tool() {
+        assert_eq!(DetectorProfile::for_tool("claude").name, "claude");
+        assert_eq!(DetectorProfile::for_tool("/usr/local/bin/codex").name, "codex");
+        assert_eq!(DetectorProfile::for_tool("aider").name, "aider");
+        assert_eq!(DetectorProfile::for_tool("gemini").name, "gemini");
+        assert_eq!(DetectorProfile::for_tool("my-agent").name, "generic");
+        assert_eq!(DetectorProfile::by_name("CODEX").unwrap().name, "codex");
+        assert!(DetectorProfile::by_name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_codex_profile() {
+        let screen = "Would you like to run the following command?\n$ cargo test\n› 1. Yes, proceed\n  2. No, and tell Codex what to do differently";
+        assert_eq!(detect(&CODEX, screen), Some(SessionState::AwaitingApproval));
+        assert_eq!(detect(&CODEX, "Done.\n› Ask Codex to do anything\n⏎ send"), Some(SessionState::Idle));
+        // Claude's prompt shape means nothing to the codex profile
+        assert_eq!(detect(&CODEX, "Do you want to proceed?"), None);
+    }
+
+    #[test]
+    fn test_aider_profile() {
+        assert_eq!(
+            detect(&AIDER, "Add src/main.rs to the chat? (Y)es/(N)o/(D)on't ask again [Yes]:"),
+            Some(SessionState::AwaitingApproval)
+        );
+        assert_eq!(detect(&AIDER, "Tokens: 2k sent\narchitect> "), Some(SessionState::Idle));
+        assert_eq!(detect(&AIDER, "architect> add tests"), None);
+    }
+
+    #[test]
+    fn test_gemini_profile() {
+        let screen = "│ >   Type your message or @path/to/file │\n╰──────╯\n~/repo   no sandbox   gemini-2.5-pro";
+        assert_eq!(detect(&GEMINI, screen), Some(SessionState::Idle));
+        assert_eq!(detect(&GEMINI, "Allow execution?\n● Yes, allow once"), Some(SessionState::AwaitingApproval));
+    }
+
+    #[test]
+    fn test_generic_profile() {
+        assert_eq!(detect(&GENERIC, "Overwrite file? (y/n)"), Some(SessionState::AwaitingApproval));
+        assert_eq!(detect(&GENERIC, "output\n$ "), Some(SessionState::Idle));
+    }
+}
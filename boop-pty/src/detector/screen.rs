@@ -0,0 +1,170 @@
+use vt100::Parser;
+
+pub const DEFAULT_ROWS: u16 = 24;
+pub const DEFAULT_COLS: u16 = 80;
+
+// vt100 misbehaves on degenerate grids (e.g. scrolling a single row)
+const MIN_SIZE: u16 = 2;
+
+/// Unknown sizes (0, as reported without a controlling terminal) use the defaults
+fn normalize_size(rows: u16, cols: u16) -> (u16, u16) {
+    if rows == 0 || cols == 0 {
+        return (DEFAULT_ROWS, DEFAULT_COLS);
+    }
+    (rows.max(MIN_SIZE), cols.max(MIN_SIZE))
+}
+
+/// Virtual terminal that mirrors what the wrapped tool has drawn on screen.
+///
+/// Cursor movement, `\r` redraws and full-screen repaints are applied to a
+/// rows×cols grid, so callers see the rendered result rather than the raw
+/// byte stream.
+pub struct Screen {
+    parser: Parser,
+}
+
+impl Screen {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        let (rows, cols) = normalize_size(rows, cols);
+        Self {
+            parser: Parser::new(rows, cols, 0),
+        }
+    }
+
+    pub fn process(&mut self, data: &[u8]) {
+        self.parser.process(data);
+    }
+
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        let (rows, cols) = normalize_size(rows, cols);
+        self.parser.set_size(rows, cols);
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        self.parser.screen().size()
+    }
+
+    /// Window title last set by the program with OSC 0 or 2
+    pub fn title(&self) -> &str {
+        self.parser.screen().title()
+    }
+
+    /// Bytes that redraw this screen on a blank terminal of the same size:
+    /// contents, cursor, input modes and title
+    pub fn snapshot(&self) -> Vec<u8> {
+        let screen = self.parser.screen();
+        let mut out = Vec::new();
+        if screen.alternate_screen() {
+            out.extend(b"\x1b[?1049h");
+        }
+        out.extend(screen.state_formatted());
+        out
+    }
+
+    /// Every visible row, with trailing whitespace removed
+    pub fn lines(&self) -> Vec<String> {
+        let (_, cols) = self.size();
+        self.parser
+            .screen()
+            .rows(0, cols)
+            .map(|row| row.trim_end().to_string())
+            .collect()
+    }
+
+    /// Text on the cursor's row, left of the cursor
+    pub fn cursor_line(&self) -> String {
+        let screen = self.parser.screen();
+        let (row, col) = screen.cursor_position();
+        if col == 0 {
+            return String::new();
+        }
+        screen.contents_between(row, 0, row, col)
+    }
+
+    /// Visible rows up to the last non-empty one, joined with newlines
+    pub fn text(&self) -> String {
+        let lines = self.lines();
+        let end = lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        lines[..end].join("\n")
+    }
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self::new(DEFAULT_ROWS, DEFAULT_COLS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_carriage_return_redraw() {
+        let mut screen = Screen::new(5, 40);
+        screen.process(b"Thinking...\rDo you want to proceed?");
+        assert_eq!(screen.lines()[0], "Do you want to proceed?");
+    }
+
+    #[test]
+    fn test_cursor_movement_repaint() {
+        let mut screen = Screen::new(5, 40);
+        screen.process(b"line one\r\nline two\r\n");
+        // Move cursor up two rows and overwrite the first line
+        screen.process(b"\x1b[2A\x1b[2K> ");
+        assert_eq!(screen.text(), ">\nline two");
+    }
+
+    #[test]
+    fn test_clear_screen() {
+        let mut screen = Screen::new(5, 40);
+        screen.process(b"old output\r\n");
+        screen.process(b"\x1b[2J\x1b[Hfresh");
+        assert_eq!(screen.text(), "fresh");
+    }
+
+    #[test]
+    fn test_cursor_line() {
+        let mut screen = Screen::new(5, 40);
+        screen.process(b"output\r\n> ");
+        assert_eq!(screen.cursor_line(), "> ");
+        screen.process(b"\r\n");
+        assert_eq!(screen.cursor_line(), "");
+    }
+
+    #[test]
+    fn test_snapshot_redraws_screen() {
+        let mut screen = Screen::new(5, 40);
+        screen.process(b"\x1b[?1049h\x1b]0;claude\x07Do you want to proceed?\r\n> 1. Yes");
+
+        // A terminal that attaches later sees the same screen
+        let mut attached = Screen::new(5, 40);
+        attached.process(&screen.snapshot());
+        assert_eq!(attached.lines(), screen.lines());
+        assert_eq!(attached.cursor_line(), "> 1. Yes");
+        assert_eq!(attached.title(), "claude");
+        assert!(attached.parser.screen().alternate_screen());
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut screen = Screen::new(5, 40);
+        screen.resize(10, 100);
+        assert_eq!(screen.size(), (10, 100));
+        assert_eq!(screen.lines().len(), 10);
+    }
+
+    #[test]
+    fn test_degenerate_sizes() {
+        let mut screen = Screen::new(0, 0);
+        assert_eq!(screen.size(), (DEFAULT_ROWS, DEFAULT_COLS));
+
+        screen.resize(1, 1);
+        screen.process(b"working\r\nmore output\r\n");
+        assert_eq!(screen.size(), (MIN_SIZE, MIN_SIZE));
+    }
+}
@@ -24,3 +24,19 @@ impl std::fmt::Display for SessionState {
         write!(f, "{}", self.as_str())
     }
 }
+
+impl std::str::FromStr for SessionState {
+    type Err = ();
+
+    /// Accepts the wire names in any case, e.g. "AWAITING_APPROVAL" or "idle"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "WORKING" => Ok(SessionState::Working),
+            "AWAITING_APPROVAL" => Ok(SessionState::AwaitingApproval),
+            "IDLE" => Ok(SessionState::Idle),
+            "COMPLETED" => Ok(SessionState::Completed),
+            "ERROR" => Ok(SessionState::Error),
+            _ => Err(()),
+        }
+    }
+}
@@ -11,6 +11,18 @@ pub enum BoopError {
     #[error("IPC error: {0}")]
     Ipc(String),
 
+    #[error("Config error: {0}")]
+    Config(String),
+
+    #[error("Notification error: {0}")]
+    Notify(String),
+
+    #[error("Recording error: {0}")]
+    Recording(String),
+
+    #[error("Audit log error: {0}")]
+    Audit(String),
+
     #[error("Invalid arguments: {0}")]
     InvalidArgs(String),
 }
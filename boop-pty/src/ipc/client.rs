@@ -1,31 +1,105 @@
 use crate::error::{BoopError, Result};
-use crate::ipc::protocol::Message;
-use std::io::Write;
+use crate::ipc::protocol::{Command, Message};
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::fd::AsRawFd;
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
+type CommandHandler = Arc<dyn Fn(Command) + Send + Sync>;
+
+/// Connection to the app's socket. It stays open for the whole session so
+/// the app can send commands back; if the app restarts, the next message
+/// reconnects.
 pub struct IpcClient {
     socket_path: PathBuf,
+    stream: Option<UnixStream>,
+    on_command: Option<CommandHandler>,
+    /// START message, re-sent on a new connection so a restarted app learns
+    /// about the session again
+    announce: Option<Message>,
+    /// Whether the last message was written to a connection the app still
+    /// had open
+    delivered: bool,
 }
 
 impl IpcClient {
     pub fn new() -> Self {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-        let socket_path = PathBuf::from(home).join(".boop").join("sock");
-        Self { socket_path }
+        Self::with_socket_path(crate::paths::boop_dir().join("sock"))
+    }
+
+    pub fn with_socket_path(socket_path: PathBuf) -> Self {
+        Self {
+            socket_path,
+            stream: None,
+            on_command: None,
+            announce: None,
+            delivered: false,
+        }
+    }
+
+    /// Call `handler` on a background thread for each command received
+    pub fn on_command(&mut self, handler: impl Fn(Command) + Send + Sync + 'static) {
+        self.on_command = Some(Arc::new(handler));
+    }
+
+    /// Whether the last message reached the app
+    pub fn delivered(&self) -> bool {
+        self.delivered
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<()> {
+        if let Message::Start { .. } = message {
+            self.announce = Some(message.clone());
+        }
+        self.delivered = false;
+
+        // Writing into a socket the app has closed still succeeds locally
+        if self.stream.as_ref().is_some_and(peer_closed) {
+            self.disconnect();
+        }
+
+        let data = message.serialize();
+        if let Some(stream) = self.stream.as_mut() {
+            if stream.write_all(data.as_bytes()).is_ok() {
+                self.delivered = true;
+                return Ok(());
+            }
+            // The app went away; try a fresh connection once
+            self.disconnect();
+        }
+
+        if !self.connect()? {
+            // Socket not available - app might not be running, silently ignore
+            return Ok(());
+        }
+
+        let stream = self.stream.as_mut().expect("connected");
+        if let Some(announce) = self.announce.as_ref().filter(|_| !matches!(message, Message::Start { .. })) {
+            stream.write_all(announce.serialize().as_bytes()).map_err(|e| {
+                BoopError::Ipc(format!("Failed to send message: {}", e))
+            })?;
+        }
+        stream.write_all(data.as_bytes()).map_err(|e| {
+            BoopError::Ipc(format!("Failed to send message: {}", e))
+        })?;
+
+        self.delivered = true;
+        Ok(())
     }
 
-    pub fn send(&self, message: &Message) -> Result<()> {
-        // Try to connect with timeout
+    /// Open the socket and start reading commands; false if the app is not listening
+    fn connect(&mut self) -> Result<bool> {
         let stream = match UnixStream::connect(&self.socket_path) {
             Ok(s) => s,
             Err(e) => {
-                // Socket not available - app might not be running, silently ignore
                 if e.kind() == std::io::ErrorKind::NotFound
                     || e.kind() == std::io::ErrorKind::ConnectionRefused
                 {
-                    return Ok(());
+                    return Ok(false);
                 }
                 return Err(BoopError::Ipc(format!(
                     "Failed to connect to socket: {}",
@@ -36,13 +110,48 @@ impl IpcClient {
 
         stream.set_write_timeout(Some(Duration::from_secs(1)))?;
 
-        let mut stream = stream;
-        let data = message.serialize();
-        stream.write_all(data.as_bytes()).map_err(|e| {
-            BoopError::Ipc(format!("Failed to send message: {}", e))
-        })?;
+        if let Some(handler) = self.on_command.clone() {
+            let reader = stream.try_clone()?;
+            thread::spawn(move || Self::read_commands(reader, handler));
+        }
 
-        Ok(())
+        self.stream = Some(stream);
+        Ok(true)
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            // Also ends the reader thread
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn read_commands(stream: UnixStream, handler: CommandHandler) {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if let Some(command) = Command::parse(&line) {
+                handler(command);
+            }
+        }
+    }
+}
+
+/// Whether the other end has closed `stream`. Only peeks, so commands
+/// waiting to be read are left for the reader thread.
+fn peer_closed(stream: &UnixStream) -> bool {
+    let mut byte = 0u8;
+    let read = unsafe {
+        libc::recv(
+            stream.as_raw_fd(),
+            (&mut byte as *mut u8).cast(),
+            1,
+            libc::MSG_PEEK | libc::MSG_DONTWAIT,
+        )
+    };
+    match read {
+        0 => true,
+        -1 => !matches!(std::io::Error::last_os_error().kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted),
+        _ => false,
     }
 }
 
@@ -51,3 +160,103 @@ impl Default for IpcClient {
         Self::new()
     }
 }
+
+impl Drop for IpcClient {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc;
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("boop-ipc-{}-{}.sock", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_app_not_running() {
+        let mut client = IpcClient::with_socket_path(socket_path("missing"));
+        assert!(client.send(&Message::end("abc123", 0)).is_ok());
+        assert!(!client.delivered());
+    }
+
+    #[test]
+    fn test_app_closed_is_not_delivered() {
+        let path = socket_path("closed");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut client = IpcClient::with_socket_path(path.clone());
+        client.send(&Message::start("abc123", "claude", "boop", 1)).unwrap();
+        assert!(client.delivered());
+
+        // The app quits: nothing is listening any more
+        let (app, _) = listener.accept().unwrap();
+        drop(listener);
+        let _ = std::fs::remove_file(&path);
+        drop(app);
+
+        let state = Message::state("abc123", crate::detector::SessionState::AwaitingApproval, "");
+        assert!(client.send(&state).is_ok());
+        assert!(!client.delivered());
+    }
+
+    #[test]
+    fn test_persistent_connection_receives_commands() {
+        let path = socket_path("commands");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let mut client = IpcClient::with_socket_path(path.clone());
+        client.on_command(move |command| {
+            let _ = tx.send(command);
+        });
+
+        client.send(&Message::start("abc123", "claude", "boop", 1)).unwrap();
+        client.send(&Message::end("abc123", 0)).unwrap();
+
+        // Both messages arrive on the one connection
+        let (mut app, _) = listener.accept().unwrap();
+        let mut lines = BufReader::new(app.try_clone().unwrap()).lines();
+        assert!(lines.next().unwrap().unwrap().contains("\"type\":\"START\""));
+        assert!(lines.next().unwrap().unwrap().contains("\"type\":\"END\""));
+
+        app.write_all(b"{\"type\":\"RESPOND\",\"session_id\":\"abc123\",\"keys\":\"1\"}\n").unwrap();
+        let command = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(command.keys(), b"1");
+
+        drop(client);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_reconnect_announces_session() {
+        let path = socket_path("reconnect");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut client = IpcClient::with_socket_path(path.clone());
+        client.send(&Message::start("abc123", "claude", "boop", 1)).unwrap();
+        let (first, _) = listener.accept().unwrap();
+
+        // The app restarts: the old connection is gone
+        first.shutdown(Shutdown::Both).unwrap();
+        drop(first);
+        let state = Message::state("abc123", crate::detector::SessionState::Idle, "");
+        client.send(&state).unwrap();
+        assert!(client.delivered());
+
+        let (second, _) = listener.accept().unwrap();
+        let mut lines = BufReader::new(second).lines();
+        assert!(lines.next().unwrap().unwrap().contains("\"type\":\"START\""));
+        assert!(lines.next().unwrap().unwrap().contains("\"type\":\"STATE\""));
+
+        drop(client);
+        let _ = std::fs::remove_file(&path);
+    }
+}
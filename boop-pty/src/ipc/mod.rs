@@ -2,4 +2,4 @@ mod client;
 mod protocol;
 
 pub use client::IpcClient;
-pub use protocol::Message;
+pub use protocol::{Command, Message};
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-use crate::detector::SessionState;
+use crate::detector::{ApprovalRequest, SessionState};
+use crate::tmux::TmuxPane;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -9,12 +10,31 @@ pub enum Message {
         tool: String,
         project_name: String,
         pid: u32,
+        tmux: Option<TmuxPane>,  // Set when running inside tmux
     },
     State {
         session_id: String,
         state: SessionState,
         details: String,
         working_duration_secs: Option<u64>,  // Duration spent in working state before this state change
+        stalled_secs: Option<u64>,  // Set while working but silent for this long
+        approval: Option<ApprovalRequest>,  // Parsed permission dialog, for AWAITING_APPROVAL
+        focused: Option<bool>,  // Whether the terminal's tab is in front, once it has said
+    },
+    /// Still awaiting approval, `waiting_secs` after it was reported;
+    /// `level` counts up from 1 with each reminder
+    Escalate {
+        session_id: String,
+        level: u32,
+        waiting_secs: u64,
+    },
+    /// The user typed into the session for the first time since it
+    /// reported `state`, `response_ms` later; whatever notified them of
+    /// it can be cleared
+    Ack {
+        session_id: String,
+        state: SessionState,
+        response_ms: u64,
     },
     End {
         session_id: String,
@@ -22,6 +42,72 @@ pub enum Message {
     },
 }
 
+/// Commands the app sends back over a session's connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Type `keys` into the session, e.g. "1" or "y\r" to answer a prompt
+    Respond { session_id: String, keys: String },
+    /// Send Ctrl-C to the session
+    Interrupt { session_id: String },
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonCommand {
+    #[serde(rename = "type")]
+    cmd_type: String,
+    session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keys: Option<String>,
+}
+
+impl Command {
+    /// Parse one line; unknown or malformed commands are ignored
+    pub fn parse(line: &str) -> Option<Self> {
+        let json: JsonCommand = serde_json::from_str(line.trim()).ok()?;
+        match json.cmd_type.as_str() {
+            "RESPOND" => Some(Command::Respond {
+                session_id: json.session_id,
+                keys: json.keys?,
+            }),
+            "INTERRUPT" => Some(Command::Interrupt {
+                session_id: json.session_id,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        match self {
+            Command::Respond { session_id, .. } | Command::Interrupt { session_id } => session_id,
+        }
+    }
+
+    /// Bytes to write to the PTY master
+    pub fn keys(&self) -> &[u8] {
+        match self {
+            Command::Respond { keys, .. } => keys.as_bytes(),
+            Command::Interrupt { .. } => b"\x03",
+        }
+    }
+
+    /// One line, as the app writes it
+    pub fn serialize(&self) -> String {
+        let json = match self {
+            Command::Respond { session_id, keys } => JsonCommand {
+                cmd_type: "RESPOND".to_string(),
+                session_id: session_id.clone(),
+                keys: Some(keys.clone()),
+            },
+            Command::Interrupt { session_id } => JsonCommand {
+                cmd_type: "INTERRUPT".to_string(),
+                session_id: session_id.clone(),
+                keys: None,
+            },
+        };
+        format!("{}\n", serde_json::to_string(&json).unwrap())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct JsonMessage {
     #[serde(rename = "type")]
@@ -41,15 +127,34 @@ struct JsonMessage {
     exit_code: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     working_duration_secs: Option<u64>,  // Duration spent in working state before this state change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stalled_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    approval: Option<ApprovalRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tmux: Option<TmuxPane>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    waiting_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    focused: Option<bool>,
 }
 
 impl Message {
     pub fn start(session_id: &str, tool: &str, project_name: &str, pid: u32) -> Self {
+        Self::start_with_tmux(session_id, tool, project_name, pid, None)
+    }
+
+    pub fn start_with_tmux(session_id: &str, tool: &str, project_name: &str, pid: u32, tmux: Option<TmuxPane>) -> Self {
         Self::Start {
             session_id: session_id.to_string(),
             tool: tool.to_string(),
             project_name: project_name.to_string(),
             pid,
+            tmux,
         }
     }
 
@@ -59,6 +164,9 @@ impl Message {
             state,
             details: details.to_string(),
             working_duration_secs: None,
+            stalled_secs: None,
+            approval: None,
+            focused: None,
         }
     }
 
@@ -68,6 +176,64 @@ impl Message {
             state,
             details: details.to_string(),
             working_duration_secs,
+            stalled_secs: None,
+            approval: None,
+            focused: None,
+        }
+    }
+
+    /// Still working, but no output for `silent_secs`
+    pub fn stalled(session_id: &str, silent_secs: u64) -> Self {
+        Self::State {
+            session_id: session_id.to_string(),
+            state: SessionState::Working,
+            details: format!("No output for {}s", silent_secs),
+            working_duration_secs: None,
+            stalled_secs: Some(silent_secs),
+            approval: None,
+            focused: None,
+        }
+    }
+
+    /// AWAITING_APPROVAL with what the permission dialog is asking for
+    pub fn approval(
+        session_id: &str,
+        details: &str,
+        working_duration_secs: Option<u64>,
+        request: ApprovalRequest,
+    ) -> Self {
+        Self::State {
+            session_id: session_id.to_string(),
+            state: SessionState::AwaitingApproval,
+            details: details.to_string(),
+            working_duration_secs,
+            stalled_secs: None,
+            approval: Some(request),
+            focused: None,
+        }
+    }
+
+    /// This message with the terminal's focus, if it is a STATE
+    pub fn with_focus(mut self, is_focused: Option<bool>) -> Self {
+        if let Self::State { focused, .. } = &mut self {
+            *focused = is_focused;
+        }
+        self
+    }
+
+    pub fn escalate(session_id: &str, level: u32, waiting_secs: u64) -> Self {
+        Self::Escalate {
+            session_id: session_id.to_string(),
+            level,
+            waiting_secs,
+        }
+    }
+
+    pub fn ack(session_id: &str, state: SessionState, response_ms: u64) -> Self {
+        Self::Ack {
+            session_id: session_id.to_string(),
+            state,
+            response_ms,
         }
     }
 
@@ -78,6 +244,44 @@ impl Message {
         }
     }
 
+    /// Parse one line as sent by `serialize`; `None` for anything malformed
+    pub fn parse(line: &str) -> Option<Self> {
+        let json: JsonMessage = serde_json::from_str(line.trim()).ok()?;
+        match json.msg_type.as_str() {
+            "START" => Some(Message::Start {
+                session_id: json.session_id,
+                tool: json.tool?,
+                project_name: json.project_name?,
+                pid: json.pid?,
+                tmux: json.tmux,
+            }),
+            "STATE" => Some(Message::State {
+                session_id: json.session_id,
+                state: json.state?.parse().ok()?,
+                details: json.details.unwrap_or_default(),
+                working_duration_secs: json.working_duration_secs,
+                stalled_secs: json.stalled_secs,
+                approval: json.approval,
+                focused: json.focused,
+            }),
+            "ESCALATE" => Some(Message::Escalate {
+                session_id: json.session_id,
+                level: json.level?,
+                waiting_secs: json.waiting_secs?,
+            }),
+            "ACK" => Some(Message::Ack {
+                session_id: json.session_id,
+                state: json.state?.parse().ok()?,
+                response_ms: json.response_ms?,
+            }),
+            "END" => Some(Message::End {
+                session_id: json.session_id,
+                exit_code: json.exit_code?,
+            }),
+            _ => None,
+        }
+    }
+
     pub fn serialize(&self) -> String {
         let json = match self {
             Message::Start {
@@ -85,6 +289,7 @@ impl Message {
                 tool,
                 project_name,
                 pid,
+                tmux,
             } => JsonMessage {
                 msg_type: "START".to_string(),
                 session_id: session_id.clone(),
@@ -95,12 +300,22 @@ impl Message {
                 details: None,
                 exit_code: None,
                 working_duration_secs: None,
+                stalled_secs: None,
+                approval: None,
+                tmux: tmux.clone(),
+                level: None,
+                waiting_secs: None,
+                response_ms: None,
+                focused: None,
             },
             Message::State {
                 session_id,
                 state,
                 details,
                 working_duration_secs,
+                stalled_secs,
+                approval,
+                focused,
             } => JsonMessage {
                 msg_type: "STATE".to_string(),
                 session_id: session_id.clone(),
@@ -111,6 +326,57 @@ impl Message {
                 details: Some(details.clone()),
                 exit_code: None,
                 working_duration_secs: *working_duration_secs,
+                stalled_secs: *stalled_secs,
+                approval: approval.clone(),
+                tmux: None,
+                level: None,
+                waiting_secs: None,
+                response_ms: None,
+                focused: *focused,
+            },
+            Message::Escalate {
+                session_id,
+                level,
+                waiting_secs,
+            } => JsonMessage {
+                msg_type: "ESCALATE".to_string(),
+                session_id: session_id.clone(),
+                tool: None,
+                project_name: None,
+                pid: None,
+                state: None,
+                details: None,
+                exit_code: None,
+                working_duration_secs: None,
+                stalled_secs: None,
+                approval: None,
+                tmux: None,
+                level: Some(*level),
+                waiting_secs: Some(*waiting_secs),
+                response_ms: None,
+                focused: None,
+            },
+            Message::Ack {
+                session_id,
+                state,
+                response_ms,
+            } => JsonMessage {
+                msg_type: "ACK".to_string(),
+                session_id: session_id.clone(),
+                tool: None,
+                project_name: None,
+                pid: None,
+                state: Some(state.as_str().to_string()),
+                details: None,
+                exit_code: None,
+                working_duration_secs: None,
+                stalled_secs: None,
+                approval: None,
+                tmux: None,
+                level: None,
+                waiting_secs: None,
+                response_ms: Some(*response_ms),
+                focused: None,
             },
             Message::End {
                 session_id,
@@ -125,6 +391,13 @@ impl Message {
                 details: None,
                 exit_code: Some(*exit_code),
                 working_duration_secs: None,
+                stalled_secs: None,
+                approval: None,
+                tmux: None,
+                level: None,
+                waiting_secs: None,
+                response_ms: None,
+                focused: None,
             },
         };
         format!("{}\n", serde_json::to_string(&json).unwrap())
@@ -156,6 +429,71 @@ mod tests {
         assert!(serialized.contains("\"details\":\"Waiting for input\""));
     }
 
+    #[test]
+    fn test_stalled_message() {
+        let msg = Message::stalled("abc123", 300);
+        let serialized = msg.serialize();
+        assert!(serialized.contains("\"state\":\"WORKING\""));
+        assert!(serialized.contains("\"stalled_secs\":300"));
+
+        let msg = Message::state("abc123", SessionState::Working, "Output resumed");
+        assert!(!msg.serialize().contains("stalled_secs"));
+    }
+
+    #[test]
+    fn test_focused_state() {
+        let msg = Message::state("abc123", SessionState::Idle, "").with_focus(Some(true));
+        let serialized = msg.serialize();
+        assert!(serialized.contains("\"focused\":true"));
+        assert!(matches!(Message::parse(&serialized).unwrap(), Message::State { focused: Some(true), .. }));
+
+        // Not known until the terminal reports
+        assert!(!Message::state("abc123", SessionState::Idle, "").serialize().contains("focused"));
+        assert!(!Message::end("abc123", 0).with_focus(Some(false)).serialize().contains("focused"));
+    }
+
+    #[test]
+    fn test_approval_message() {
+        let request = ApprovalRequest {
+            tool: Some("Bash".to_string()),
+            command: Some("git status".to_string()),
+            choices: vec!["Yes".to_string(), "No".to_string()],
+            keys: vec!["1".to_string(), "2".to_string()],
+            ..Default::default()
+        };
+        let msg = Message::approval("abc123", "Bash: git status", Some(42), request);
+        let parsed: serde_json::Value = serde_json::from_str(msg.serialize().trim()).unwrap();
+        assert_eq!(parsed["state"], "AWAITING_APPROVAL");
+        assert_eq!(parsed["working_duration_secs"], 42);
+        assert_eq!(parsed["approval"]["tool"], "Bash");
+        assert_eq!(parsed["approval"]["command"], "git status");
+        assert_eq!(parsed["approval"]["choices"][1], "No");
+        assert_eq!(parsed["approval"]["keys"][1], "2");
+        assert!(parsed["approval"].get("path").is_none());
+    }
+
+    #[test]
+    fn test_escalate_message() {
+        let serialized = Message::escalate("abc123", 2, 600).serialize();
+        assert_eq!(serialized, "{\"type\":\"ESCALATE\",\"session_id\":\"abc123\",\"level\":2,\"waiting_secs\":600}\n");
+        assert!(matches!(
+            Message::parse(&serialized).unwrap(),
+            Message::Escalate { level: 2, waiting_secs: 600, .. }
+        ));
+        assert!(Message::parse(r#"{"type":"ESCALATE","session_id":"abc123","level":1}"#).is_none());
+    }
+
+    #[test]
+    fn test_ack_message() {
+        let serialized = Message::ack("abc123", SessionState::Idle, 8421).serialize();
+        assert_eq!(serialized, "{\"type\":\"ACK\",\"session_id\":\"abc123\",\"state\":\"IDLE\",\"response_ms\":8421}\n");
+        assert!(matches!(
+            Message::parse(&serialized).unwrap(),
+            Message::Ack { state: SessionState::Idle, response_ms: 8421, .. }
+        ));
+        assert!(Message::parse(r#"{"type":"ACK","session_id":"abc123","state":"IDLE"}"#).is_none());
+    }
+
     #[test]
     fn test_end_message() {
         let msg = Message::end("abc123", 0);
@@ -174,6 +512,61 @@ mod tests {
         assert!(serialized.contains("\\\"quotes\\\""));
     }
 
+    #[test]
+    fn test_parse_round_trip() {
+        let msg = Message::stalled("abc123", 300);
+        match Message::parse(&msg.serialize()).unwrap() {
+            Message::State { state, stalled_secs, details, .. } => {
+                assert_eq!(state, SessionState::Working);
+                assert_eq!(stalled_secs, Some(300));
+                assert_eq!(details, "No output for 300s");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let msg = Message::parse(&Message::start("abc123", "claude", "boop", 42).serialize()).unwrap();
+        assert!(matches!(msg, Message::Start { pid: 42, tmux: None, .. }));
+
+        let pane = TmuxPane {
+            session: "work".to_string(),
+            window: "3".to_string(),
+            pane: "1".to_string(),
+            pane_id: "%12".to_string(),
+        };
+        let serialized = Message::start_with_tmux("abc123", "claude", "boop", 42, Some(pane.clone())).serialize();
+        assert!(serialized.contains("\"tmux\":{\"session\":\"work\""));
+        assert!(matches!(Message::parse(&serialized).unwrap(), Message::Start { tmux: Some(p), .. } if p == pane));
+
+        // Required fields and known states only
+        assert!(Message::parse(r#"{"type":"START","session_id":"abc123"}"#).is_none());
+        assert!(Message::parse(r#"{"type":"STATE","session_id":"abc123","state":"NAPPING"}"#).is_none());
+        assert!(Message::parse("START|abc123|claude|boop|42").is_none());
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let command = Command::parse(r#"{"type":"RESPOND","session_id":"abc123","keys":"1\r"}"#).unwrap();
+        assert_eq!(
+            command,
+            Command::Respond {
+                session_id: "abc123".to_string(),
+                keys: "1\r".to_string(),
+            }
+        );
+        assert_eq!(command.keys(), b"1\r");
+        assert_eq!(Command::parse(&command.serialize()), Some(command));
+
+        let command = Command::parse("{\"type\":\"INTERRUPT\",\"session_id\":\"abc123\"}\n").unwrap();
+        assert_eq!(command.session_id(), "abc123");
+        assert_eq!(command.keys(), b"\x03");
+        assert_eq!(Command::parse(&command.serialize()), Some(command));
+
+        // RESPOND needs keys; unknown types and garbage are ignored
+        assert!(Command::parse(r#"{"type":"RESPOND","session_id":"abc123"}"#).is_none());
+        assert!(Command::parse(r#"{"type":"REBOOT","session_id":"abc123"}"#).is_none());
+        assert!(Command::parse("not json").is_none());
+    }
+
     #[test]
     fn test_valid_json_output() {
         let msg = Message::start("test", "claude", "project", 1234);
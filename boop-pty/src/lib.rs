@@ -0,0 +1,16 @@
+//! Shared by the `boop-pty` wrapper and the `boop-daemon` socket server
+
+pub mod attach;
+pub mod audit;
+pub mod daemon;
+pub mod detector;
+pub mod error;
+pub mod ipc;
+pub mod notify;
+pub mod paths;
+pub mod policy;
+pub mod pty;
+pub mod recording;
+pub mod terminal;
+pub mod tmux;
+pub mod web;
@@ -1,26 +1,85 @@
-mod detector;
-mod error;
-mod ipc;
-mod pty;
-mod terminal;
-
-use crate::error::{BoopError, Result};
-use crate::pty::PtyHandler;
+use boop_pty::attach;
+use boop_pty::audit;
+use boop_pty::detector::{DetectorConfig, DetectorProfile, SessionState, StateDetector};
+use boop_pty::error::{BoopError, Result};
+use boop_pty::policy::Policy;
+use boop_pty::pty::{HandlerOptions, PtyHandler, TerminalAlert};
+use boop_pty::recording::{Cast, Replay};
+use boop_pty::terminal;
 use signal_hook::consts::signal::{SIGHUP, SIGINT, SIGTERM};
 use signal_hook::iterator::Signals;
 use std::env;
-use std::process;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{self, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
 fn print_usage() {
-    eprintln!("Usage: boop-pty <session_id> <tool> [args...]");
+    eprintln!("Usage: boop-pty [options] <session_id> <tool> [args...]");
+    eprintln!("       boop-pty [options] run [--detached] <tool> [args...]");
+    eprintln!("       boop-pty attach <session_id>");
+    eprintln!("       boop-pty watch <session_id>");
+    eprintln!("       boop-pty audit verify [<head hash>]");
+    eprintln!("       boop-pty [options] replay <file.cast> [--expect <states>]");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --profile <name>  Detector profile: {} (default: from tool)", DetectorProfile::names().join(", "));
+    eprintln!("  --record          Record the session to ~/.boop/recordings/<session_id>.cast");
+    eprintln!("  --title           Show the session state in this terminal's title");
+    eprintln!("  --audit           Log permission dialogs and their answers to ~/.boop/audit/");
+    eprintln!("  --escalate <list> Remind again while an approval waits, e.g. 2,10,30 (minutes)");
+    eprintln!("  --alert <kind>    Alert this terminal on approval and idle: {}", TerminalAlert::names().join(", "));
+    eprintln!("  --no-focus        Notify even while this terminal's tab is in front");
+    eprintln!("  -h, --help        Show this help");
     eprintln!();
     eprintln!("Arguments:");
     eprintln!("  session_id  Unique identifier for this session");
     eprintln!("  tool        The command to run (e.g., 'claude', 'codex')");
     eprintln!("  args        Additional arguments to pass to the tool");
     eprintln!();
+    eprintln!("Detached sessions:");
+    eprintln!("  run makes up a session id. With --detached, the tool keeps running in");
+    eprintln!("  the background (under 'boop-pty serve') after this terminal closes, and");
+    eprintln!("  the session id is printed. attach connects any terminal to it; several");
+    eprintln!("  can attach at once. Ctrl-\\ detaches.");
+    eprintln!();
+    eprintln!("Watch:");
+    eprintln!("  Mirrors any running session in this terminal, current screen first,");
+    eprintln!("  without being able to type into it. q or Ctrl-C stops watching.");
+    eprintln!();
+    eprintln!("Audit:");
+    eprintln!("  Each permission dialog is logged with what was typed until the session");
+    eprintln!("  moved on, to ~/.boop/audit/<date>.jsonl (UTC). Entries are hash-chained;");
+    eprintln!("  audit verify exits 1 at the first one that was changed or removed, and");
+    eprintln!("  prints the newest entry's hash. Given a hash it printed before, it also");
+    eprintln!("  exits 1 if that entry is gone, i.e. the newest entries were removed.");
+    eprintln!();
+    eprintln!("Replay:");
+    eprintln!("  Runs a recording through the detector and prints each state change");
+    eprintln!("  with the rule that caused it. --expect takes a comma-separated list");
+    eprintln!("  of states, e.g. AWAITING_APPROVAL,WORKING,IDLE, and exits 1 on mismatch.");
+    eprintln!();
     eprintln!("Environment:");
-    eprintln!("  BOOP_PROJECT  Override project name (default: git repo or directory name)");
+    eprintln!("  BOOP_PROJECT        Override project name (default: git repo or directory name)");
+    eprintln!("  BOOP_PROFILE        Detector profile, same as --profile");
+    eprintln!("  BOOP_RECORD         Set to 1 to record, same as --record");
+    eprintln!("  BOOP_ALERT          Terminal alert, same as --alert");
+    eprintln!("  BOOP_TITLE          Set to 1 to show state in the title, same as --title");
+    eprintln!("  BOOP_AUDIT          Set to 1 to keep the audit log, same as --audit");
+    eprintln!("  BOOP_ESCALATE       Reminder times in minutes, same as --escalate");
+    eprintln!("  BOOP_FOCUS_TRACKING Set to 0 to notify regardless of focus, same as --no-focus");
+    eprintln!("  BOOP_FOCUS_COMMAND  Run when a desktop notification is clicked (Linux)");
+    eprintln!();
+    eprintln!("Files:");
+    eprintln!("  ~/.boop/patterns.toml  Extra detection rules and overrides");
+    eprintln!("  ~/.boop/webhooks.toml  Webhooks to notify when the app isn't running");
+    eprintln!("  ~/.boop/policy.toml    Permission dialogs to answer automatically");
+    eprintln!("  ~/.boop/policy.log     Every automatic answer, as JSON lines");
+    eprintln!("  ~/.boop/recordings/    Session recordings (asciicast v2)");
+    eprintln!("  ~/.boop/sessions/      Session sockets, and logs of detached sessions");
 }
 
 fn get_project_name() -> String {
@@ -36,7 +95,7 @@ fn get_project_name() -> String {
     {
         if output.status.success() {
             if let Ok(path) = String::from_utf8(output.stdout) {
-                if let Some(name) = path.trim().split('/').last() {
+                if let Some(name) = path.trim().split('/').next_back() {
                     return name.to_string();
                 }
             }
@@ -55,31 +114,332 @@ fn get_project_name() -> String {
 }
 
 fn run() -> Result<i32> {
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args().skip(1).peekable();
+    let mut profile_name = env::var("BOOP_PROFILE").ok();
+    let mut options = HandlerOptions {
+        record: env::var("BOOP_RECORD").is_ok_and(|v| !v.is_empty() && v != "0"),
+        alert: None,
+        title: env::var("BOOP_TITLE").is_ok_and(|v| !v.is_empty() && v != "0"),
+        detached: false,
+        policy: Policy::default(),
+        audit: env::var("BOOP_AUDIT").is_ok_and(|v| !v.is_empty() && v != "0"),
+        escalate_after: Vec::new(),
+        focus: env::var("BOOP_FOCUS_TRACKING").map_or(true, |v| v != "0"),
+    };
+    // Handed on to the background session by `run --detached`
+    let mut forwarded = Vec::new();
+    if let Some(name) = env::var("BOOP_ALERT").ok().filter(|v| !v.is_empty()) {
+        options.alert = Some(parse_alert(&name)?);
+    }
+    if let Some(list) = env::var("BOOP_ESCALATE").ok().filter(|v| !v.is_empty()) {
+        options.escalate_after = parse_minutes(&list)?;
+    }
+
+    // Options come before the session id; everything after the tool is passed through
+    while let Some(arg) = args.next_if(|a| a.starts_with('-')) {
+        match arg.as_str() {
+            "--help" | "-h" => {
+                print_usage();
+                return Ok(0);
+            }
+            "--profile" => {
+                let name = args.next().ok_or_else(|| {
+                    BoopError::InvalidArgs("--profile requires a value".to_string())
+                })?;
+                forwarded.extend(["--profile".to_string(), name.clone()]);
+                profile_name = Some(name);
+            }
+            "--record" => {
+                options.record = true;
+                forwarded.push(arg);
+            }
+            "--title" => {
+                options.title = true;
+                forwarded.push(arg);
+            }
+            "--audit" => {
+                options.audit = true;
+                forwarded.push(arg);
+            }
+            "--no-focus" => {
+                options.focus = false;
+            }
+            "--alert" => {
+                let name = args.next().ok_or_else(|| {
+                    BoopError::InvalidArgs("--alert requires a value".to_string())
+                })?;
+                options.alert = Some(parse_alert(&name)?);
+                forwarded.extend([arg, name]);
+            }
+            "--escalate" => {
+                let list = args.next().ok_or_else(|| {
+                    BoopError::InvalidArgs("--escalate requires a list of minutes".to_string())
+                })?;
+                options.escalate_after = parse_minutes(&list)?;
+                forwarded.extend([arg, list]);
+            }
+            other => {
+                print_usage();
+                return Err(BoopError::InvalidArgs(format!("Unknown option: {}", other)));
+            }
+        }
+    }
 
-    if args.len() < 3 {
-        print_usage();
-        return Err(BoopError::InvalidArgs(
-            "Missing required arguments".to_string(),
-        ));
+    if args.next_if_eq("replay").is_some() {
+        return replay(args, profile_name);
     }
 
-    // Handle --help
-    if args.iter().any(|a| a == "--help" || a == "-h") {
-        print_usage();
-        return Ok(0);
+    if args.next_if_eq("audit").is_some() {
+        return match args.next().as_deref() {
+            Some("verify") => {
+                let dir = audit::audit_dir();
+                let anchor = args.next();
+                let verified = audit::verify(&dir, anchor.as_deref())?;
+                println!(
+                    "{}: {} entries in {} files, chain intact",
+                    dir.display(),
+                    verified.entries,
+                    verified.files
+                );
+                if !verified.head.is_empty() {
+                    println!("head {}", verified.head);
+                }
+                Ok(0)
+            }
+            _ => Err(BoopError::InvalidArgs("usage: boop-pty audit verify [<head hash>]".to_string())),
+        };
     }
 
-    let session_id = args[1].clone();
-    let tool = args[2].clone();
-    let tool_args: Vec<String> = args[3..].to_vec();
+    if args.next_if_eq("attach").is_some() {
+        let session_id = args.next().ok_or_else(|| {
+            BoopError::InvalidArgs("attach requires a session id".to_string())
+        })?;
+        return attach::attach(&session_id);
+    }
+
+    if args.next_if_eq("watch").is_some() {
+        let session_id = args.next().ok_or_else(|| {
+            BoopError::InvalidArgs("watch requires a session id".to_string())
+        })?;
+        return attach::watch(&session_id);
+    }
+
+    // `run` makes up a session id; `serve` is the background half of
+    // `run --detached`
+    let mut detach = false;
+    let session_id = match args.peek().map(String::as_str) {
+        Some("run") => {
+            args.next();
+            detach = args.next_if_eq("--detached").is_some();
+            Some(new_session_id()?)
+        }
+        Some("serve") => {
+            args.next();
+            options.detached = true;
+            args.next()
+        }
+        _ => args.next(),
+    };
+
+    let (session_id, tool) = match (session_id, args.next()) {
+        (Some(session_id), Some(tool)) => (session_id, tool),
+        _ => {
+            print_usage();
+            return Err(BoopError::InvalidArgs(
+                "Missing required arguments".to_string(),
+            ));
+        }
+    };
+    let tool_args: Vec<String> = args.collect();
+
+    if detach {
+        return start_detached(&session_id, &tool, &tool_args, &forwarded);
+    }
 
+    let detector = StateDetector::with_config(load_config(profile_name, &tool)?);
+    // Without its rules nothing is answered automatically, which is safe
+    options.policy = Policy::load().unwrap_or_else(|e| {
+        eprintln!("boop-pty: ignoring approval policy: {}", e);
+        Policy::default()
+    });
     let project_name = get_project_name();
 
-    let mut handler = PtyHandler::new(session_id, tool, project_name);
+    let mut handler = PtyHandler::new(session_id, tool, project_name, detector, options);
     handler.run(&tool_args)
 }
 
+/// A random UUID, like the shell hook makes
+fn new_session_id() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    // Version 4, RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]))
+}
+
+/// Start `serve` in the background, in a session of its own so closing
+/// this terminal doesn't hang it up, and wait until it can be attached to
+fn start_detached(session_id: &str, tool: &str, tool_args: &[String], options: &[String]) -> Result<i32> {
+    fs::create_dir_all(attach::sessions_dir())?;
+    let log_path = attach::log_path(session_id);
+    let log = File::create(&log_path)?;
+
+    let mut command = process::Command::new(env::current_exe()?);
+    command
+        .args(options)
+        .arg("serve")
+        .arg(session_id)
+        .arg(tool)
+        .args(tool_args)
+        .env("BOOP_PROJECT", get_project_name())
+        // The pane it was started from isn't where it will be watched
+        .env_remove("TMUX")
+        .env_remove("TMUX_PANE")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log);
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = command.spawn()?;
+
+    let socket = attach::socket_path(session_id);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !socket.exists() {
+        if let Some(status) = child.try_wait()? {
+            return Err(BoopError::Pty(format!(
+                "Session exited at startup ({}); see {}",
+                status,
+                log_path.display()
+            )));
+        }
+        if Instant::now() > deadline {
+            return Err(BoopError::Pty(format!(
+                "Session did not start; see {}",
+                log_path.display()
+            )));
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    println!("{}", session_id);
+    eprintln!("boop-pty: {} is running detached; attach with: boop-pty attach {}", tool, session_id);
+    Ok(0)
+}
+
+fn parse_alert(name: &str) -> Result<TerminalAlert> {
+    name.parse().map_err(|_| {
+        BoopError::InvalidArgs(format!(
+            "Unknown alert '{}' (available: {})",
+            name,
+            TerminalAlert::names().join(", ")
+        ))
+    })
+}
+
+/// "2,10,30" as minutes
+fn parse_minutes(list: &str) -> Result<Vec<Duration>> {
+    list.split(',')
+        .map(|minutes| {
+            minutes
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|minutes| *minutes > 0.0 && minutes.is_finite())
+                .map(|minutes| Duration::from_secs_f64(minutes * 60.0))
+                .ok_or_else(|| BoopError::InvalidArgs(format!("Not a number of minutes: '{}'", minutes.trim())))
+        })
+        .collect()
+}
+
+/// Detector rules and timing for `tool`, or for the profile named on the command line
+fn load_config(profile_name: Option<String>, tool: &str) -> Result<DetectorConfig> {
+    let profile = match profile_name {
+        Some(name) => DetectorProfile::by_name(&name).ok_or_else(|| {
+            BoopError::InvalidArgs(format!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                DetectorProfile::names().join(", ")
+            ))
+        })?,
+        None => DetectorProfile::for_tool(tool),
+    };
+
+    // A broken rules file must not stop the wrapped tool from starting
+    Ok(DetectorConfig::load(profile).unwrap_or_else(|e| {
+        eprintln!("boop-pty: ignoring custom patterns: {}", e);
+        DetectorConfig::for_profile(profile)
+    }))
+}
+
+fn replay(mut args: impl Iterator<Item = String>, profile_name: Option<String>) -> Result<i32> {
+    let path = args
+        .next()
+        .ok_or_else(|| BoopError::InvalidArgs("replay requires a .cast file".to_string()))?;
+
+    let mut expected = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--expect" => {
+                let list = args.next().ok_or_else(|| {
+                    BoopError::InvalidArgs("--expect requires a list of states".to_string())
+                })?;
+                let states = list
+                    .split(',')
+                    .map(|name| {
+                        name.trim().parse::<SessionState>().map_err(|_| {
+                            BoopError::InvalidArgs(format!("Unknown state '{}'", name.trim()))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                expected = Some(states);
+            }
+            other => {
+                return Err(BoopError::InvalidArgs(format!("Unknown replay option: {}", other)));
+            }
+        }
+    }
+
+    let cast = Cast::load(Path::new(&path))?;
+    let config = load_config(profile_name, &cast.title)?;
+    let replay = Replay::run(&cast, config);
+
+    for (time, change) in &replay.timeline {
+        println!("{:>9.3}s  {}", time, change);
+    }
+
+    let states = replay.states();
+    let recorded = cast.markers();
+    if !recorded.is_empty() && recorded != states {
+        println!("note: the live session reported {}", join_states(&recorded));
+    }
+
+    match expected {
+        Some(expected) if expected != states => {
+            eprintln!("boop-pty: replay mismatch");
+            eprintln!("  expected: {}", join_states(&expected));
+            eprintln!("    actual: {}", join_states(&states));
+            Ok(1)
+        }
+        _ => Ok(0),
+    }
+}
+
+fn join_states(states: &[SessionState]) -> String {
+    if states.is_empty() {
+        return "(none)".to_string();
+    }
+    states.iter().map(|state| state.as_str()).collect::<Vec<_>>().join(", ")
+}
+
 fn main() {
     // Save original terminal settings FIRST
     terminal::save_terminal_settings();
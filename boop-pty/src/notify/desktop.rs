@@ -0,0 +1,400 @@
+use crate::detector::SessionState;
+use crate::error::{BoopError, Result};
+use crate::notify::{Notification, Notifier};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::Value;
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+// Key of the "Show" button; "default" is a click on the notification itself
+const FOCUS_ACTION: &str = "focus";
+
+// Urgency hint levels from the notification spec
+const URGENCY_NORMAL: u8 = 1;
+const URGENCY_CRITICAL: u8 = 2;
+
+type FocusHandler = Arc<dyn Fn(&Notification) + Send + Sync>;
+
+/// Notifications on screen, so a session's next one replaces its last and a
+/// clicked one can be traced back to its session
+#[derive(Default)]
+struct Shown {
+    by_session: HashMap<String, u32>,
+    by_id: HashMap<u32, Notification>,
+}
+
+/// Desktop popups through the freedesktop notification service on the
+/// session bus, for Linux workstations
+pub struct DesktopNotifier {
+    connection: Connection,
+    /// The server draws action buttons
+    actions: bool,
+    /// The server renders `<b>`-style markup, so text must be escaped
+    markup: bool,
+    shown: Arc<Mutex<Shown>>,
+}
+
+impl DesktopNotifier {
+    /// Connect to the session bus; fails on a headless machine
+    pub fn connect() -> Result<Self> {
+        Self::with_connection(Connection::session().map_err(dbus_error)?)
+    }
+
+    /// Connect to the bus at `address`, e.g. a private `dbus-daemon`
+    pub fn connect_to(address: &str) -> Result<Self> {
+        let connection = zbus::blocking::connection::Builder::address(address)
+            .and_then(|builder| builder.build())
+            .map_err(dbus_error)?;
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        // Also checks that a notification server is running at all
+        let capabilities: Vec<String> = connection
+            .call_method(Some(DESTINATION), PATH, Some(INTERFACE), "GetCapabilities", &())
+            .and_then(|reply| reply.body().deserialize())
+            .map_err(dbus_error)?;
+
+        Ok(Self {
+            connection,
+            actions: capabilities.iter().any(|c| c == "actions"),
+            markup: capabilities.iter().any(|c| c == "body-markup"),
+            shown: Arc::new(Mutex::new(Shown::default())),
+        })
+    }
+
+    /// Call `handler` on a background thread when the user clicks a
+    /// notification or its "Show" button
+    pub fn on_focus(&self, handler: impl Fn(&Notification) + Send + Sync + 'static) -> Result<()> {
+        let rule = zbus::MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(INTERFACE)
+            .and_then(|rule| rule.path(PATH))
+            .map(|rule| rule.build())
+            .map_err(dbus_error)?;
+        let signals = MessageIterator::for_match_rule(rule, &self.connection, None).map_err(dbus_error)?;
+
+        let shown = self.shown.clone();
+        let handler: FocusHandler = Arc::new(handler);
+        thread::spawn(move || Self::read_signals(signals, &shown, &handler));
+        Ok(())
+    }
+
+    fn read_signals(signals: MessageIterator, shown: &Mutex<Shown>, handler: &FocusHandler) {
+        for message in signals {
+            let Ok(message) = message else { break };
+            let header = message.header();
+            match header.member().map(|member| member.as_str()) {
+                Some("ActionInvoked") => {
+                    let Ok((id, _action)) = message.body().deserialize::<(u32, String)>() else {
+                        continue;
+                    };
+                    let notification = shown.lock().unwrap().by_id.get(&id).cloned();
+                    if let Some(notification) = notification {
+                        handler(&notification);
+                    }
+                }
+                Some("NotificationClosed") => {
+                    if let Ok((id, _reason)) = message.body().deserialize::<(u32, u32)>() {
+                        shown.lock().unwrap().forget(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn body(&self, notification: &Notification) -> String {
+        let mut body = notification.message.clone();
+        if !notification.details.is_empty() {
+            body.push('\n');
+            body.push_str(&notification.details);
+        }
+        if self.markup {
+            body = body.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        }
+        body
+    }
+}
+
+impl Shown {
+    fn forget(&mut self, id: u32) {
+        if let Some(notification) = self.by_id.remove(&id) {
+            if self.by_session.get(&notification.session_id) == Some(&id) {
+                self.by_session.remove(&notification.session_id);
+            }
+        }
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &str {
+        "desktop"
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        // A clean exit is usually the user quitting the tool themselves
+        if notification.state == SessionState::Completed {
+            return Ok(());
+        }
+
+        let urgency = match notification.state {
+            SessionState::AwaitingApproval | SessionState::Error => URGENCY_CRITICAL,
+            _ => URGENCY_NORMAL,
+        };
+        let mut hints: HashMap<&str, Value> = HashMap::new();
+        hints.insert("urgency", Value::U8(urgency));
+        let actions: Vec<&str> = if self.actions {
+            vec!["default", "Show", FOCUS_ACTION, "Show"]
+        } else {
+            Vec::new()
+        };
+        let replaces = self
+            .shown
+            .lock()
+            .unwrap()
+            .by_session
+            .get(&notification.session_id)
+            .copied()
+            .unwrap_or(0);
+
+        let id: u32 = self
+            .connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "Notify",
+                &(
+                    "Boop",
+                    replaces,
+                    "",
+                    notification.title.as_str(),
+                    self.body(notification),
+                    actions,
+                    hints,
+                    -1i32,
+                ),
+            )
+            .and_then(|reply| reply.body().deserialize())
+            .map_err(dbus_error)?;
+
+        let mut shown = self.shown.lock().unwrap();
+        shown.forget(replaces);
+        shown.by_session.insert(notification.session_id.clone(), id);
+        shown.by_id.insert(id, notification.clone());
+        Ok(())
+    }
+
+    fn dismiss(&self, session_id: &str) -> Result<()> {
+        let Some(id) = self.shown.lock().unwrap().by_session.get(session_id).copied() else {
+            return Ok(());
+        };
+        self.connection
+            .call_method(Some(DESTINATION), PATH, Some(INTERFACE), "CloseNotification", &(id,))
+            .map_err(dbus_error)?;
+        self.shown.lock().unwrap().forget(id);
+        Ok(())
+    }
+}
+
+/// What clicking a desktop notification does: runs `$BOOP_FOCUS_COMMAND`
+/// through `sh` if set, with the session in `BOOP_SESSION_ID`, `BOOP_PID`,
+/// `BOOP_TOOL`, `BOOP_PROJECT` and `BOOP_TMUX_TARGET`. Otherwise switches
+/// tmux to the session's pane, or raises its terminal outside tmux.
+pub fn focus_session(notification: &Notification) -> Result<()> {
+    let pid = notification.pid.unwrap_or(0);
+    let Ok(command) = std::env::var("BOOP_FOCUS_COMMAND") else {
+        return match &notification.tmux_target {
+            Some(target) => run(Command::new("tmux").args(["switch-client", "-t", target])),
+            None => focus_terminal(pid),
+        };
+    };
+
+    run(Command::new("sh")
+        .args(["-c", &command])
+        .env("BOOP_SESSION_ID", &notification.session_id)
+        .env("BOOP_PID", pid.to_string())
+        .env("BOOP_TOOL", &notification.tool)
+        .env("BOOP_PROJECT", &notification.title)
+        .env("BOOP_TMUX_TARGET", notification.tmux_target.as_deref().unwrap_or("")))
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let status = command.status()?;
+    if !status.success() {
+        return Err(BoopError::Notify(format!("focus command failed: {}", status)));
+    }
+    Ok(())
+}
+
+/// Raise the terminal window running the session. Walks up from the
+/// `boop-pty` process to the first ancestor that owns an X11 window, using
+/// `xdotool`; on Wayland use a focus command instead.
+pub fn focus_terminal(pid: u32) -> Result<()> {
+    let mut current = Some(pid);
+    while let Some(pid) = current.filter(|pid| *pid > 1) {
+        let output = Command::new("xdotool")
+            .args(["search", "--pid", &pid.to_string()])
+            .output()
+            .map_err(|e| BoopError::Notify(format!("xdotool: {}", e)))?;
+        if let Some(window) = String::from_utf8_lossy(&output.stdout).lines().next() {
+            Command::new("xdotool").args(["windowactivate", window]).status()?;
+            return Ok(());
+        }
+        current = parent_pid(pid);
+    }
+    Err(BoopError::Notify(format!("no window found for process {}", pid)))
+}
+
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces; fields resume after its ')'
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn dbus_error(e: zbus::Error) -> BoopError {
+    BoopError::Notify(format!("D-Bus: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::NotificationKind;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Stdio};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    type Call = (u32, String, String, Vec<String>, u8);
+
+    /// Stand-in notification server recording each `Notify` call
+    struct FakeServer {
+        calls: mpsc::Sender<Call>,
+        next_id: u32,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl FakeServer {
+        fn get_capabilities(&self) -> Vec<String> {
+            vec!["actions".to_string(), "body".to_string()]
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &mut self,
+            _app_name: String,
+            replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            body: String,
+            actions: Vec<String>,
+            hints: HashMap<String, zbus::zvariant::OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let urgency = hints.get("urgency").and_then(|v| u8::try_from(v).ok()).unwrap_or(0);
+            let _ = self.calls.send((replaces_id, summary, body, actions, urgency));
+            self.next_id += 1;
+            self.next_id
+        }
+    }
+
+    /// A private bus, so tests never touch the user's desktop
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        fn start() -> Self {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("dbus-daemon must be installed");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+            Self {
+                daemon,
+                address: address.trim().to_string(),
+            }
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon; run with --ignored"]
+    fn test_notify_over_private_bus() {
+        let bus = Bus::start();
+        let (tx, calls) = mpsc::channel();
+        let server = zbus::blocking::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .serve_at(PATH, FakeServer { calls: tx, next_id: 0 })
+            .unwrap()
+            .name(DESTINATION)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let desktop = DesktopNotifier::connect_to(&bus.address).unwrap();
+        let (focus_tx, focused) = mpsc::channel();
+        desktop
+            .on_focus(move |notification| {
+                let _ = focus_tx.send(notification.session_id.clone());
+            })
+            .unwrap();
+
+        let approval = Notification {
+            details: "Bash: rm -rf build".to_string(),
+            ..Notification::new(NotificationKind::Approval, "abc123", "claude", "boop")
+        };
+        desktop.notify(&approval).unwrap();
+        let (replaces, summary, body, actions, urgency) = calls.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(replaces, 0);
+        assert_eq!(summary, "boop");
+        assert_eq!(body, "Claude is waiting for approval\nBash: rm -rf build");
+        assert!(actions.contains(&FOCUS_ACTION.to_string()));
+        assert_eq!(urgency, URGENCY_CRITICAL);
+
+        // Finishing after work replaces the approval popup
+        let idle = Notification {
+            state: SessionState::Idle,
+            ..Notification::new(NotificationKind::Completed, "abc123", "claude", "boop")
+        };
+        desktop.notify(&idle).unwrap();
+        let (replaces, _, _, _, urgency) = calls.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(replaces, 1);
+        assert_eq!(urgency, URGENCY_NORMAL);
+
+        // A clean exit doesn't pop up
+        desktop
+            .notify(&Notification::new(NotificationKind::Completed, "abc123", "claude", "boop"))
+            .unwrap();
+        assert!(calls.recv_timeout(Duration::from_millis(200)).is_err());
+
+        server
+            .emit_signal(None::<&str>, PATH, INTERFACE, "ActionInvoked", &(2u32, FOCUS_ACTION))
+            .unwrap();
+        assert_eq!(focused.recv_timeout(Duration::from_secs(5)).unwrap(), "abc123");
+    }
+
+    #[test]
+    fn test_parent_pid() {
+        assert_eq!(parent_pid(std::process::id()), Some(nix::unistd::getppid().as_raw() as u32));
+        assert_eq!(parent_pid(0), None);
+    }
+}
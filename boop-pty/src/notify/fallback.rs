@@ -0,0 +1,140 @@
+use crate::daemon::Registry;
+use crate::ipc::Message;
+use crate::notify::{dispatch, Notification, Notifier};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+// Longest a finished session waits for notifications still being delivered
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Notifies straight from `boop-pty` when neither the app nor `boop-daemon`
+/// is listening. Every message is tracked so the rules see the whole
+/// session, but only undelivered ones notify; delivery happens on a worker
+/// thread so a slow server never stalls the terminal.
+pub struct Fallback {
+    registry: Registry,
+    worker: Option<mpsc::Sender<Notification>>,
+    done: mpsc::Receiver<()>,
+    /// Notifications handed to the worker and not yet delivered
+    pending: Arc<AtomicUsize>,
+}
+
+impl Fallback {
+    pub fn new(notifiers: Vec<Box<dyn Notifier>>) -> Self {
+        let (tx, rx) = mpsc::channel::<Notification>();
+        let (done_tx, done) = mpsc::channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let delivered = pending.clone();
+        thread::spawn(move || {
+            for notification in rx {
+                dispatch(&notifiers, &notification);
+                delivered.fetch_sub(1, Ordering::SeqCst);
+            }
+            let _ = done_tx.send(());
+        });
+
+        Self {
+            registry: Registry::new(),
+            worker: Some(tx),
+            done,
+            pending,
+        }
+    }
+
+    /// Track `message`; notify if it calls for it and nobody else received it
+    pub fn observe(&mut self, message: &Message, delivered: bool) {
+        let notification = self.registry.apply(message.clone());
+        if let Some(notification) = notification.filter(|_| !delivered) {
+            if let Some(worker) = &self.worker {
+                self.pending.fetch_add(1, Ordering::SeqCst);
+                if worker.send(notification).is_err() {
+                    self.pending.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Fallback {
+    fn drop(&mut self) {
+        // The final notification usually comes with END, just before exit.
+        // Only wait if one is still on its way, and not for long.
+        self.worker.take();
+        if self.pending.load(Ordering::SeqCst) > 0 {
+            let _ = self.done.recv_timeout(FLUSH_TIMEOUT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::SessionState;
+    use crate::error::Result;
+    use crate::notify::NotificationKind;
+    use std::sync::{Arc, Mutex};
+
+    struct Collect(Arc<Mutex<Vec<Notification>>>);
+
+    impl Notifier for Collect {
+        fn name(&self) -> &str {
+            "collect"
+        }
+
+        fn notify(&self, notification: &Notification) -> Result<()> {
+            self.0.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_notifies_only_undelivered() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let mut fallback = Fallback::new(vec![Box::new(Collect(sent.clone()))]);
+
+        fallback.observe(&Message::start("abc123", "claude", "boop", 1), true);
+        fallback.observe(&Message::state("abc123", SessionState::AwaitingApproval, ""), true);
+        fallback.observe(&Message::state("abc123", SessionState::Working, ""), false);
+        // The app went away mid-session
+        fallback.observe(&Message::end("abc123", 2), false);
+        drop(fallback);
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].kind, NotificationKind::Error);
+        assert_eq!(sent[0].message, "Claude encountered an error");
+    }
+
+    struct Hang;
+
+    impl Notifier for Hang {
+        fn name(&self) -> &str {
+            "hang"
+        }
+
+        fn notify(&self, _notification: &Notification) -> Result<()> {
+            thread::sleep(Duration::from_secs(30));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_exit_is_not_held_up() {
+        use std::time::Instant;
+
+        // Nothing to deliver: no wait at all
+        let started = Instant::now();
+        drop(Fallback::new(vec![Box::new(Hang)]));
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        // A notifier that never finishes holds exit up for the flush timeout at most
+        let mut fallback = Fallback::new(vec![Box::new(Hang)]);
+        fallback.observe(&Message::start("abc123", "claude", "boop", 1), false);
+        fallback.observe(&Message::end("abc123", 2), false);
+        let started = Instant::now();
+        drop(fallback);
+        assert!(started.elapsed() < FLUSH_TIMEOUT + Duration::from_millis(500));
+    }
+}
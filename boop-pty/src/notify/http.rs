@@ -0,0 +1,105 @@
+use crate::error::{BoopError, Result};
+use std::thread;
+use std::time::Duration;
+
+// Per request; a stuck server shouldn't hold a notification for long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many times to try a publish, and how long to wait before the first
+/// retry; each later wait doubles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub attempts: u32,
+    pub initial: Duration,
+}
+
+impl Backoff {
+    fn delay(&self, retry: u32) -> Duration {
+        self.initial * 2u32.saturating_pow(retry)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial: Duration::from_secs(1),
+        }
+    }
+}
+
+pub(crate) fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build()
+}
+
+/// POST `body` with a fresh request from `request` until it succeeds or
+/// attempts run out. Network trouble, 429 and server errors are retried;
+/// any other refusal is final.
+pub(crate) fn send_with_retry(backoff: &Backoff, request: impl Fn() -> ureq::Request, body: &str) -> Result<()> {
+    let mut retry = 0;
+    loop {
+        let request = request();
+        let url = request.url().to_string();
+        let error = match request.send_string(body) {
+            Ok(_) => return Ok(()),
+            Err(ureq::Error::Status(code, _)) if code == 429 || code >= 500 => format!("HTTP {}", code),
+            Err(ureq::Error::Status(code, _)) => {
+                return Err(BoopError::Notify(format!("{}: HTTP {}", url, code)));
+            }
+            Err(e) => e.to_string(),
+        };
+
+        if retry + 1 >= backoff.attempts {
+            return Err(BoopError::Notify(format!("{}: {}", url, error)));
+        }
+        thread::sleep(backoff.delay(retry));
+        retry += 1;
+    }
+}
+
+/// Local HTTP server standing in for ntfy or a webhook in tests
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::sync::mpsc;
+    use std::thread;
+
+    pub struct Request {
+        pub url: String,
+        pub headers: Vec<(String, String)>,
+        pub body: String,
+    }
+
+    impl Request {
+        pub fn header(&self, name: &str) -> &str {
+            self.headers
+                .iter()
+                .find(|(field, _)| field.eq_ignore_ascii_case(name))
+                .map_or("", |(_, value)| value)
+        }
+    }
+
+    /// Answers each request with the next status; returns the base URL
+    pub fn server(statuses: Vec<u16>) -> (String, mpsc::Receiver<Request>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for status in statuses {
+                let Ok(mut request) = server.recv() else { break };
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let _ = tx.send(Request {
+                    url: request.url().to_string(),
+                    headers: request
+                        .headers()
+                        .iter()
+                        .map(|h| (h.field.to_string(), h.value.to_string()))
+                        .collect(),
+                    body,
+                });
+                let _ = request.respond(tiny_http::Response::empty(status));
+            }
+        });
+        (address, rx)
+    }
+}
@@ -0,0 +1,261 @@
+#[cfg(target_os = "linux")]
+mod desktop;
+mod fallback;
+mod http;
+mod ntfy;
+mod settings;
+mod template;
+mod webhook;
+
+#[cfg(target_os = "linux")]
+pub use desktop::{focus_session, focus_terminal, DesktopNotifier};
+pub use fallback::Fallback;
+pub use http::Backoff;
+pub use ntfy::NtfyNotifier;
+pub use settings::{KindSettings, NtfySettings, Priority, Settings};
+pub use template::{Escape, Template};
+pub use webhook::WebhookNotifier;
+
+use crate::detector::SessionState;
+use crate::error::Result;
+
+/// Only notify that a session went idle if it had been working this long;
+/// quick answers don't need a ping
+pub const MIN_WORKING_SECS_FOR_IDLE: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    Approval,
+    Completed,
+    Error,
+}
+
+impl NotificationKind {
+    /// Name used in config files and webhook payloads
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::Approval => "approval",
+            NotificationKind::Completed => "completed",
+            NotificationKind::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for NotificationKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "approval" => Ok(NotificationKind::Approval),
+            "completed" => Ok(NotificationKind::Completed),
+            "error" => Ok(NotificationKind::Error),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A notification ready to be delivered, e.g. "boop: Claude finished"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub session_id: String,
+    /// Project name
+    pub title: String,
+    pub message: String,
+    pub tool: String,
+    /// The state that triggered it; `Idle` for work that finished at a prompt
+    pub state: SessionState,
+    pub details: String,
+    pub working_duration_secs: Option<u64>,
+    /// Set when the tool exited
+    pub exit_code: Option<i32>,
+    /// The `boop-pty` process, when known
+    pub pid: Option<u32>,
+    /// `tmux switch-client -t` target of the session's pane, e.g. "%12"
+    pub tmux_target: Option<String>,
+    /// Set on reminders that an approval is still waiting, counting up from 1
+    pub escalation: Option<u32>,
+}
+
+impl Notification {
+    /// Whether a session moving from `previous` to `current` deserves a
+    /// notification, using the same rules as the macOS app:
+    ///
+    /// - approval always notifies
+    /// - completion notifies unless the user was just answering an approval
+    /// - going idle counts as completion, but only straight from working and
+    ///   after at least `MIN_WORKING_SECS_FOR_IDLE` of work
+    /// - errors always notify
+    pub fn for_change(
+        previous: SessionState,
+        current: SessionState,
+        working_duration_secs: Option<u64>,
+    ) -> Option<NotificationKind> {
+        if previous == current {
+            return None;
+        }
+
+        match current {
+            SessionState::AwaitingApproval => Some(NotificationKind::Approval),
+            SessionState::Completed if previous != SessionState::AwaitingApproval => {
+                Some(NotificationKind::Completed)
+            }
+            SessionState::Completed => None,
+            SessionState::Error => Some(NotificationKind::Error),
+            SessionState::Idle => {
+                // Without duration info, assume it was long enough
+                let worked_long_enough = working_duration_secs
+                    .is_none_or(|secs| secs >= MIN_WORKING_SECS_FOR_IDLE);
+                (previous == SessionState::Working && worked_long_enough)
+                    .then_some(NotificationKind::Completed)
+            }
+            SessionState::Working => None,
+        }
+    }
+
+    pub fn new(kind: NotificationKind, session_id: &str, tool: &str, project_name: &str) -> Self {
+        let name = capitalize(tool);
+        let message = match kind {
+            NotificationKind::Approval => format!("{} is waiting for approval", name),
+            NotificationKind::Completed => format!("{} finished", name),
+            NotificationKind::Error => format!("{} encountered an error", name),
+        };
+        let state = match kind {
+            NotificationKind::Approval => SessionState::AwaitingApproval,
+            NotificationKind::Completed => SessionState::Completed,
+            NotificationKind::Error => SessionState::Error,
+        };
+
+        Self {
+            kind,
+            session_id: session_id.to_string(),
+            title: project_name.to_string(),
+            message,
+            tool: tool.to_string(),
+            state,
+            details: String::new(),
+            working_duration_secs: None,
+            exit_code: None,
+            pid: None,
+            tmux_target: None,
+            escalation: None,
+        }
+    }
+}
+
+/// Somewhere notifications are delivered to
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn notify(&self, notification: &Notification) -> Result<()>;
+
+    /// Take down what this notifier last showed for a session, now that
+    /// the user has seen to it; most can't
+    fn dismiss(&self, _session_id: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Prints notifications to stdout; always on, so the daemon's log shows
+/// what would have been sent
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        println!("[{}] {}: {}", notification.session_id, notification.title, notification.message);
+        Ok(())
+    }
+}
+
+/// Every notifier the user has set up: ntfy from the app's config, any
+/// webhooks, and desktop popups when a notification server is running. A
+/// broken webhooks file is reported and skipped.
+pub fn configured() -> Vec<Box<dyn Notifier>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(NtfyNotifier::new())];
+    match WebhookNotifier::load() {
+        Ok(webhooks) => {
+            for webhook in webhooks {
+                notifiers.push(Box::new(webhook));
+            }
+        }
+        Err(e) => eprintln!("boop: ignoring webhooks: {}", e),
+    }
+
+    // Headless machines have no session bus; that's not worth a warning
+    #[cfg(target_os = "linux")]
+    if let Ok(desktop) = DesktopNotifier::connect() {
+        let focus = desktop.on_focus(|notification| {
+            if let Err(e) = focus_session(notification) {
+                eprintln!("boop: {}", e);
+            }
+        });
+        if let Err(e) = focus {
+            eprintln!("boop: desktop notifications can't be clicked: {}", e);
+        }
+        notifiers.push(Box::new(desktop));
+    }
+
+    notifiers
+}
+
+/// Hand a notification to every notifier; one failing doesn't stop the rest
+pub fn dispatch(notifiers: &[Box<dyn Notifier>], notification: &Notification) {
+    for notifier in notifiers {
+        if let Err(e) = notifier.notify(notification) {
+            eprintln!("boop: {} notification failed: {}", notifier.name(), e);
+        }
+    }
+}
+
+/// Ask every notifier to clear a session's notification
+pub fn dismiss(notifiers: &[Box<dyn Notifier>], session_id: &str) {
+    for notifier in notifiers {
+        if let Err(e) = notifier.dismiss(session_id) {
+            eprintln!("boop: {} notification couldn't be cleared: {}", notifier.name(), e);
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SessionState::*;
+
+    #[test]
+    fn test_notification_rules() {
+        assert_eq!(Notification::for_change(Working, AwaitingApproval, Some(2)), Some(NotificationKind::Approval));
+        assert_eq!(Notification::for_change(Idle, AwaitingApproval, None), Some(NotificationKind::Approval));
+
+        assert_eq!(Notification::for_change(Working, Idle, Some(45)), Some(NotificationKind::Completed));
+        assert_eq!(Notification::for_change(Working, Idle, Some(29)), None);
+        assert_eq!(Notification::for_change(Working, Idle, None), Some(NotificationKind::Completed));
+        assert_eq!(Notification::for_change(AwaitingApproval, Idle, None), None);
+
+        assert_eq!(Notification::for_change(Working, Completed, None), Some(NotificationKind::Completed));
+        assert_eq!(Notification::for_change(AwaitingApproval, Completed, None), None);
+        assert_eq!(Notification::for_change(AwaitingApproval, Error, None), Some(NotificationKind::Error));
+
+        assert_eq!(Notification::for_change(Idle, Working, None), None);
+        assert_eq!(Notification::for_change(AwaitingApproval, AwaitingApproval, None), None);
+    }
+
+    #[test]
+    fn test_notification_text() {
+        let notification = Notification::new(NotificationKind::Approval, "abc123", "claude", "boop");
+        assert_eq!(notification.title, "boop");
+        assert_eq!(notification.message, "Claude is waiting for approval");
+        assert_eq!(Notification::new(NotificationKind::Completed, "abc123", "codex", "boop").message, "Codex finished");
+    }
+}
@@ -0,0 +1,178 @@
+use crate::error::Result;
+use crate::notify::http::{self, Backoff};
+use crate::notify::{Notification, NotificationKind, Notifier, Settings};
+use std::path::PathBuf;
+
+/// Publishes to the ntfy topic in `~/.boop/config.json`, the way the macOS
+/// app's `NotificationDispatcher` does
+pub struct NtfyNotifier {
+    config_path: PathBuf,
+    agent: ureq::Agent,
+    backoff: Backoff,
+}
+
+impl NtfyNotifier {
+    pub fn new() -> Self {
+        Self::with_config_path(Settings::config_path())
+    }
+
+    pub fn with_config_path(config_path: PathBuf) -> Self {
+        Self {
+            config_path,
+            agent: http::agent(),
+            backoff: Backoff::default(),
+        }
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+impl Default for NtfyNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &str {
+        "ntfy"
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        // Read on every notification; the app may change the topic or pause
+        // while sessions are running
+        let Some(settings) = Settings::load_from(&self.config_path)? else {
+            return Ok(());
+        };
+        let kind = settings.for_kind(notification.kind);
+        let Some(url) = settings.topic_url().filter(|_| kind.enabled && !settings.is_paused) else {
+            return Ok(());
+        };
+
+        // Each reminder about a waiting approval is louder than the last:
+        // a step up in priority, and one more siren for when that is
+        // already urgent, as approvals are by default
+        let escalation = notification.escalation.unwrap_or(0);
+        let priority = kind.priority.raised(escalation).ntfy_value().to_string();
+        let tags = tags(notification.kind, escalation);
+        http::send_with_retry(
+            &self.backoff,
+            || {
+                self.agent
+                    .post(&url)
+                    .set("Title", &notification.title)
+                    .set("Priority", &priority)
+                    .set("Tags", &tags)
+            },
+            &notification.message,
+        )
+    }
+}
+
+/// ntfy emoji tags, matching the app, and a siren per reminder
+fn tags(kind: NotificationKind, escalation: u32) -> String {
+    let tag = match kind {
+        NotificationKind::Approval => "warning",
+        NotificationKind::Completed => "white_check_mark",
+        NotificationKind::Error => "x",
+    };
+    std::iter::once(tag)
+        .chain(std::iter::repeat_n("rotating_light", escalation as usize))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::http::mock;
+    use crate::notify::Priority;
+    use std::time::Duration;
+
+    fn notifier(name: &str, config: &str) -> NtfyNotifier {
+        let path = std::env::temp_dir().join(format!("boop-ntfy-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, config).unwrap();
+        NtfyNotifier::with_config_path(path).with_backoff(Backoff {
+            attempts: 3,
+            initial: Duration::from_millis(10),
+        })
+    }
+
+    fn config(server: &str, extra: &str) -> String {
+        format!(r#"{{ "ntfy": {{ "topic": "boop-test", "server": "{}" }}{} }}"#, server, extra)
+    }
+
+    #[test]
+    fn test_publish_retries_server_errors() {
+        let (server, requests) = mock::server(vec![503, 200]);
+        let ntfy = notifier("retry", &config(&server, ""));
+        let notification = Notification::new(NotificationKind::Approval, "abc123", "claude", "boop");
+        ntfy.notify(&notification).unwrap();
+
+        let first = requests.recv().unwrap();
+        let second = requests.recv().unwrap();
+        assert_eq!(first.url, "/boop-test");
+        assert_eq!(second.header("Title"), "boop");
+        assert_eq!(second.header("Priority"), "5");
+        assert_eq!(second.header("Tags"), "warning");
+        assert_eq!(second.body, "Claude is waiting for approval");
+    }
+
+    #[test]
+    fn test_publish_gives_up() {
+        let (server, requests) = mock::server(vec![500, 500, 500, 400]);
+        let ntfy = notifier("give-up", &config(&server, ""));
+        let notification = Notification::new(NotificationKind::Error, "abc123", "claude", "boop");
+        assert!(ntfy.notify(&notification).is_err());
+        assert_eq!(requests.try_iter().count(), 3);
+
+        // A refused request isn't retried
+        assert!(ntfy.notify(&notification).is_err());
+        assert_eq!(requests.recv().unwrap().header("Tags"), "x");
+    }
+
+    #[test]
+    fn test_reminders_get_louder() {
+        let (server, requests) = mock::server(vec![200, 200, 200]);
+        let ntfy = notifier("escalation", &config(&server, ""));
+        let mut notification = Notification::new(NotificationKind::Approval, "abc123", "claude", "boop");
+        for escalation in [None, Some(1), Some(2)] {
+            notification.escalation = escalation;
+            ntfy.notify(&notification).unwrap();
+        }
+
+        // Approvals are urgent to begin with, so only the sirens can add up
+        assert_eq!(Settings::default().for_kind(NotificationKind::Approval).priority, Priority::Urgent);
+        let sent: Vec<(String, String)> = requests
+            .try_iter()
+            .map(|request| (request.header("Priority").to_string(), request.header("Tags").to_string()))
+            .collect();
+        let expected = [
+            ("5", "warning"),
+            ("5", "warning,rotating_light"),
+            ("5", "warning,rotating_light,rotating_light"),
+        ];
+        assert_eq!(sent, expected.map(|(priority, tags)| (priority.to_string(), tags.to_string())));
+    }
+
+    #[test]
+    fn test_settings_respected() {
+        let (server, requests) = mock::server(vec![200]);
+        let completed_off = r#", "notifications": { "completed": { "enabled": false, "priority": "low" } }"#;
+        let ntfy = notifier("settings", &config(&server, completed_off));
+        ntfy.notify(&Notification::new(NotificationKind::Completed, "abc123", "claude", "boop")).unwrap();
+        ntfy.notify(&Notification::new(NotificationKind::Error, "abc123", "claude", "boop")).unwrap();
+        assert_eq!(requests.recv().unwrap().header("Priority"), "4");
+
+        let paused = notifier("paused", &config(&server, r#", "isPaused": true"#));
+        paused.notify(&Notification::new(NotificationKind::Approval, "abc123", "claude", "boop")).unwrap();
+        assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+
+        // No config yet: nothing to publish to
+        let missing = NtfyNotifier::with_config_path(PathBuf::from("/nonexistent/config.json"));
+        assert!(missing.notify(&Notification::new(NotificationKind::Approval, "a", "claude", "boop")).is_ok());
+    }
+}
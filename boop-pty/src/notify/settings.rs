@@ -0,0 +1,173 @@
+use crate::error::{BoopError, Result};
+use crate::notify::NotificationKind;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// ntfy priority, as the app stores it ("urgent", "high", ...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Min,
+    Low,
+    Default,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    /// The number ntfy expects in its `Priority` header
+    pub fn ntfy_value(self) -> u8 {
+        match self {
+            Priority::Min => 1,
+            Priority::Low => 2,
+            Priority::Default => 3,
+            Priority::High => 4,
+            Priority::Urgent => 5,
+        }
+    }
+
+    /// `steps` levels higher, up to urgent
+    pub fn raised(self, steps: u32) -> Self {
+        const ORDER: [Priority; 5] = [Priority::Min, Priority::Low, Priority::Default, Priority::High, Priority::Urgent];
+        let index = ORDER.iter().position(|priority| *priority == self).unwrap_or(0);
+        ORDER[(index + steps as usize).min(ORDER.len() - 1)]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NtfySettings {
+    #[serde(default)]
+    pub topic: String,
+    #[serde(default = "default_server")]
+    pub server: String,
+}
+
+impl Default for NtfySettings {
+    fn default() -> Self {
+        Self {
+            topic: String::new(),
+            server: default_server(),
+        }
+    }
+}
+
+fn default_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct KindSettings {
+    pub enabled: bool,
+    pub priority: Priority,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+struct NotificationPreferences {
+    approval: KindSettings,
+    completed: KindSettings,
+    error: KindSettings,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            approval: KindSettings { enabled: true, priority: Priority::Urgent },
+            completed: KindSettings { enabled: true, priority: Priority::Default },
+            error: KindSettings { enabled: true, priority: Priority::High },
+        }
+    }
+}
+
+/// The parts of the app's `~/.boop/config.json` needed to publish to ntfy.
+/// Missing fields take the app's defaults; fields only the app uses are
+/// ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub ntfy: NtfySettings,
+    notifications: NotificationPreferences,
+    pub is_paused: bool,
+}
+
+impl Settings {
+    pub fn config_path() -> PathBuf {
+        crate::paths::boop_dir().join("config.json")
+    }
+
+    /// Settings from `path`; `None` if the file doesn't exist yet
+    pub fn load_from(path: &Path) -> Result<Option<Self>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Self::from_json(&contents)
+            .map(Some)
+            .map_err(|e| BoopError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self> {
+        serde_json::from_str(contents).map_err(|e| BoopError::Config(e.to_string()))
+    }
+
+    pub fn for_kind(&self, kind: NotificationKind) -> KindSettings {
+        match kind {
+            NotificationKind::Approval => self.notifications.approval,
+            NotificationKind::Completed => self.notifications.completed,
+            NotificationKind::Error => self.notifications.error,
+        }
+    }
+
+    /// Where to POST notifications; `None` until a topic is set
+    pub fn topic_url(&self) -> Option<String> {
+        let topic = self.ntfy.topic.trim();
+        if topic.is_empty() {
+            return None;
+        }
+        Some(format!("{}/{}", self.ntfy.server.trim_end_matches('/'), topic))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_app_config() {
+        let settings = Settings::from_json(
+            r#"{
+                "version": 1,
+                "ntfy": { "topic": "boop-abc", "server": "https://ntfy.example.com/" },
+                "notifications": {
+                    "approval": { "enabled": true, "priority": "high" },
+                    "completed": { "enabled": false, "priority": "low" },
+                    "error": { "enabled": true, "priority": "urgent" }
+                },
+                "quietHours": { "enabled": false, "start": "22:00", "end": "08:00" },
+                "respectDND": true,
+                "isPaused": true
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.topic_url().unwrap(), "https://ntfy.example.com/boop-abc");
+        assert_eq!(settings.for_kind(NotificationKind::Approval).priority.ntfy_value(), 4);
+        assert!(!settings.for_kind(NotificationKind::Completed).enabled);
+        assert!(settings.is_paused);
+    }
+
+    #[test]
+    fn test_defaults() {
+        let settings = Settings::from_json(r#"{ "ntfy": { "topic": "boop-abc" } }"#).unwrap();
+        assert_eq!(settings.topic_url().unwrap(), "https://ntfy.sh/boop-abc");
+        assert_eq!(settings.for_kind(NotificationKind::Approval).priority, Priority::Urgent);
+        assert_eq!(settings.for_kind(NotificationKind::Error).priority.ntfy_value(), 4);
+        assert_eq!(Priority::Default.raised(1), Priority::High);
+        assert_eq!(Priority::High.raised(3), Priority::Urgent);
+
+        assert!(Settings::default().topic_url().is_none());
+        assert!(Settings::from_json("{ \"ntfy\": 1 }").is_err());
+        assert!(Settings::load_from(Path::new("/nonexistent/config.json")).unwrap().is_none());
+    }
+}
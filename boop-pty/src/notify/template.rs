@@ -0,0 +1,172 @@
+use crate::error::{BoopError, Result};
+use crate::notify::Notification;
+
+/// Session fields a template can refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Kind,
+    SessionId,
+    Tool,
+    ProjectName,
+    State,
+    Details,
+    Message,
+    WorkingDurationSecs,
+    ExitCode,
+    TmuxTarget,
+    Escalation,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("kind", Field::Kind),
+    ("session_id", Field::SessionId),
+    ("tool", Field::Tool),
+    ("project_name", Field::ProjectName),
+    ("state", Field::State),
+    ("details", Field::Details),
+    ("message", Field::Message),
+    ("working_duration_secs", Field::WorkingDurationSecs),
+    ("exit_code", Field::ExitCode),
+    ("tmux_target", Field::TmuxTarget),
+    ("escalation", Field::Escalation),
+];
+
+impl Field {
+    fn value(self, notification: &Notification) -> String {
+        match self {
+            Field::Kind => notification.kind.as_str().to_string(),
+            Field::SessionId => notification.session_id.clone(),
+            Field::Tool => notification.tool.clone(),
+            Field::ProjectName => notification.title.clone(),
+            Field::State => notification.state.as_str().to_string(),
+            Field::Details => notification.details.clone(),
+            Field::Message => notification.message.clone(),
+            // Unknown values render empty
+            Field::WorkingDurationSecs => notification
+                .working_duration_secs
+                .map_or_else(String::new, |secs| secs.to_string()),
+            Field::ExitCode => notification.exit_code.map_or_else(String::new, |code| code.to_string()),
+            Field::TmuxTarget => notification.tmux_target.clone().unwrap_or_default(),
+            Field::Escalation => notification.escalation.map_or_else(String::new, |level| level.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// How field values are written into the rendered text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    None,
+    /// Escaped for use inside a JSON string, so details containing quotes
+    /// or newlines don't break the payload
+    Json,
+}
+
+/// Text with `{{field}}` placeholders, e.g.
+/// `{"text": "{{project_name}}: {{message}}"}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Unknown fields and unclosed placeholders are errors, so a typo shows
+    /// up when the config is loaded rather than as a blank in a message
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| BoopError::Config(format!("unclosed '{{{{' in template: {}", source)))?;
+            let name = after[..end].trim();
+            let field = FIELDS
+                .iter()
+                .find(|(field_name, _)| *field_name == name)
+                .map(|(_, field)| *field)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = FIELDS.iter().map(|(name, _)| *name).collect();
+                    BoopError::Config(format!(
+                        "unknown template field '{}' (available: {})",
+                        name,
+                        names.join(", ")
+                    ))
+                })?;
+            parts.push(Part::Field(field));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, notification: &Notification, escape: Escape) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field(field) => {
+                    let value = field.value(notification);
+                    match escape {
+                        Escape::None => out.push_str(&value),
+                        Escape::Json => {
+                            let quoted = serde_json::Value::String(value).to_string();
+                            out.push_str(&quoted[1..quoted.len() - 1]);
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::SessionState;
+    use crate::notify::NotificationKind;
+
+    fn notification() -> Notification {
+        Notification {
+            state: SessionState::Idle,
+            details: "said \"done\"\nbye".to_string(),
+            working_duration_secs: Some(42),
+            ..Notification::new(NotificationKind::Completed, "abc123", "claude", "boop")
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let template = Template::parse("{{ project_name }}: {{message}} ({{state}}, {{working_duration_secs}}s{{exit_code}})").unwrap();
+        assert_eq!(
+            template.render(&notification(), Escape::None),
+            "boop: Claude finished (IDLE, 42s)"
+        );
+
+        let json = Template::parse(r#"{"text": "{{details}}", "kind": "{{kind}}"}"#).unwrap();
+        let rendered = json.render(&notification(), Escape::Json);
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(value["text"], "said \"done\"\nbye");
+        assert_eq!(value["kind"], "completed");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{projct_name}}").is_err());
+        assert!(Template::parse("{{tool").is_err());
+        assert!(Template::parse("no fields, just { braces }").is_ok());
+    }
+}
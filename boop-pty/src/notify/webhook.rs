@@ -0,0 +1,242 @@
+use crate::error::{BoopError, Result};
+use crate::notify::http::{self, Backoff};
+use crate::notify::template::{Escape, Template};
+use crate::notify::{Notification, NotificationKind, Notifier};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhooksFile {
+    #[serde(default)]
+    webhook: Vec<WebhookConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookConfig {
+    name: Option<String>,
+    url: String,
+    /// Notification kinds to send; all of them if empty
+    #[serde(default)]
+    events: Vec<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: Option<String>,
+    content_type: Option<String>,
+}
+
+/// POSTs notifications to a URL of the user's choosing, with a body
+/// rendered from a template; covers chat bridges that only take webhooks
+pub struct WebhookNotifier {
+    name: String,
+    url: String,
+    kinds: Vec<NotificationKind>,
+    headers: Vec<(String, String)>,
+    /// `None` sends every field as a JSON object
+    body: Option<Template>,
+    content_type: String,
+    agent: ureq::Agent,
+    backoff: Backoff,
+}
+
+impl WebhookNotifier {
+    pub fn config_path() -> PathBuf {
+        crate::paths::boop_dir().join("webhooks.toml")
+    }
+
+    /// Webhooks from `~/.boop/webhooks.toml`; none if it doesn't exist
+    pub fn load() -> Result<Vec<Self>> {
+        let path = Self::config_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Self::load_from(&path)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Vec<Self>> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml(&contents).map_err(|e| BoopError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Vec<Self>> {
+        let file: WebhooksFile = toml::from_str(contents).map_err(|e| BoopError::Config(e.to_string()))?;
+        file.webhook
+            .into_iter()
+            .enumerate()
+            .map(|(index, config)| Self::from_config(index, config))
+            .collect()
+    }
+
+    fn from_config(index: usize, config: WebhookConfig) -> Result<Self> {
+        let name = config.name.unwrap_or_else(|| format!("webhook-{}", index + 1));
+        let kinds = config
+            .events
+            .iter()
+            .map(|event| {
+                event.parse().map_err(|_| {
+                    BoopError::Config(format!(
+                        "webhook '{}': unknown event '{}' (use approval, completed or error)",
+                        name, event
+                    ))
+                })
+            })
+            .collect::<Result<_>>()?;
+        let body = config
+            .body
+            .as_deref()
+            .map(Template::parse)
+            .transpose()
+            .map_err(|e| BoopError::Config(format!("webhook '{}': {}", name, e)))?;
+
+        Ok(Self {
+            name,
+            url: config.url,
+            kinds,
+            headers: config.headers.into_iter().collect(),
+            body,
+            content_type: config.content_type.unwrap_or_else(|| "application/json".to_string()),
+            agent: http::agent(),
+            backoff: Backoff::default(),
+        })
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    fn render(&self, notification: &Notification) -> String {
+        let Some(template) = &self.body else {
+            return serde_json::json!({
+                "kind": notification.kind.as_str(),
+                "session_id": notification.session_id,
+                "tool": notification.tool,
+                "project_name": notification.title,
+                "state": notification.state.as_str(),
+                "details": notification.details,
+                "message": notification.message,
+                "working_duration_secs": notification.working_duration_secs,
+                "exit_code": notification.exit_code,
+                "tmux_target": notification.tmux_target,
+                "escalation": notification.escalation,
+            })
+            .to_string();
+        };
+
+        let escape = if self.content_type.contains("json") {
+            Escape::Json
+        } else {
+            Escape::None
+        };
+        template.render(notification, escape)
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        if !self.kinds.is_empty() && !self.kinds.contains(&notification.kind) {
+            return Ok(());
+        }
+
+        let body = self.render(notification);
+        http::send_with_retry(
+            &self.backoff,
+            || {
+                let mut request = self.agent.post(&self.url).set("Content-Type", &self.content_type);
+                for (name, value) in &self.headers {
+                    request = request.set(name, value);
+                }
+                request
+            },
+            &body,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::SessionState;
+    use crate::notify::http::mock;
+    use std::time::Duration;
+
+    fn webhooks(contents: &str) -> Vec<WebhookNotifier> {
+        WebhookNotifier::from_toml(contents)
+            .unwrap()
+            .into_iter()
+            .map(|webhook| {
+                webhook.with_backoff(Backoff {
+                    attempts: 2,
+                    initial: Duration::from_millis(10),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_template_and_headers() {
+        let (server, requests) = mock::server(vec![502, 200]);
+        let config = r#"
+            [[webhook]]
+            name = "chat"
+            url = "SERVER/hooks/boop"
+            events = ["approval"]
+            headers = { Authorization = "Bearer secret" }
+            body = '{"text": "{{project_name}}: {{message}} ({{details}})"}'
+        "#;
+        let hooks = webhooks(&config.replace("SERVER", &server));
+        assert_eq!(hooks[0].name(), "chat");
+
+        let approval = Notification {
+            details: "Bash: echo \"hi\"".to_string(),
+            ..Notification::new(NotificationKind::Approval, "abc123", "claude", "boop")
+        };
+        hooks[0].notify(&approval).unwrap();
+        // Not subscribed to completion
+        hooks[0].notify(&Notification::new(NotificationKind::Completed, "abc123", "claude", "boop")).unwrap();
+
+        requests.recv().unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(request.url, "/hooks/boop");
+        assert_eq!(request.header("Authorization"), "Bearer secret");
+        assert_eq!(request.header("Content-Type"), "application/json");
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["text"], "boop: Claude is waiting for approval (Bash: echo \"hi\")");
+        assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_default_body() {
+        let (server, requests) = mock::server(vec![200]);
+        let hooks = webhooks(&format!("[[webhook]]\nurl = \"{}\"\n", server));
+        assert_eq!(hooks[0].name(), "webhook-1");
+
+        let error = Notification {
+            state: SessionState::Error,
+            exit_code: Some(2),
+            ..Notification::new(NotificationKind::Error, "abc123", "codex", "boop")
+        };
+        hooks[0].notify(&error).unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&requests.recv().unwrap().body).unwrap();
+        assert_eq!(body["kind"], "error");
+        assert_eq!(body["tool"], "codex");
+        assert_eq!(body["state"], "ERROR");
+        assert_eq!(body["exit_code"], 2);
+        assert!(body["working_duration_secs"].is_null());
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert!(WebhookNotifier::from_toml("[[webhook]]\nurl = \"http://x\"\nevents = [\"idle\"]\n").is_err());
+        assert!(WebhookNotifier::from_toml("[[webhook]]\nurl = \"http://x\"\nbody = \"{{nope}}\"\n").is_err());
+        assert!(WebhookNotifier::from_toml("[[webhook]]\nurl = \"http://x\"\nmethod = \"PUT\"\n").is_err());
+        assert!(WebhookNotifier::from_toml("").unwrap().is_empty());
+    }
+}
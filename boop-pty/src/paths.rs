@@ -0,0 +1,7 @@
+use std::path::PathBuf;
+
+/// `~/.boop`, where the socket, config and session data live
+pub fn boop_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home).join(".boop")
+}
@@ -0,0 +1,445 @@
+//! Answers permission dialogs the user has decided are always fine, or
+//! never are, from the rules in `~/.boop/policy.toml`
+
+use crate::detector::ApprovalRequest;
+use crate::error::{BoopError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// What lets one command line run more than one command, or more than the
+// rule's pattern could have meant: `git log && rm -rf ~`
+const SHELL_METACHARACTERS: &[&str] = &[";", "&", "|", "`", "$(", "<", ">", "\n"];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    rule: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    name: Option<String>,
+    tool: Option<String>,
+    command: Option<String>,
+    path: Option<String>,
+    action: String,
+    keys: Option<String>,
+    #[serde(default)]
+    allow_shell: bool,
+}
+
+/// What a rule does with a dialog it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Pick the first choice, or type the rule's keys
+    Approve,
+    /// Notify as usual, even if a later rule would answer
+    Escalate,
+    /// Pick the last choice, or type the rule's keys
+    Deny,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Approve => "approve",
+            Action::Escalate => "escalate",
+            Action::Deny => "deny",
+        }
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "approve" => Ok(Action::Approve),
+            "escalate" => Ok(Action::Escalate),
+            "deny" => Ok(Action::Deny),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PolicyRule {
+    name: String,
+    tool: Option<String>,
+    /// Matches the whole command, never just part of it
+    command: Option<Regex>,
+    /// Absolute and normalized
+    path: Option<PathBuf>,
+    action: Action,
+    keys: Option<String>,
+    /// Approve commands with shell metacharacters too
+    allow_shell: bool,
+}
+
+impl PolicyRule {
+    fn matches(&self, request: &ApprovalRequest, base: &Path) -> bool {
+        if self.action == Action::Approve
+            && (request.unclear_command || (!self.allow_shell && request.command.as_deref().is_some_and(has_metacharacters)))
+        {
+            return false;
+        }
+        let tool = self.tool.as_ref().map(|tool| request.tool.as_ref().is_some_and(|t| t.eq_ignore_ascii_case(tool)));
+        let command = self
+            .command
+            .as_ref()
+            .map(|regex| request.command.as_ref().is_some_and(|command| regex.is_match(command)));
+        let path = self
+            .path
+            .as_ref()
+            .map(|prefix| request.path.as_ref().is_some_and(|path| normalize(base, path).starts_with(prefix)));
+        [tool, command, path].into_iter().all(|matched| matched.unwrap_or(true))
+    }
+}
+
+/// What to do about one dialog, and the rule that said so
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub rule: String,
+    pub action: Action,
+    /// What to type; `None` when escalating
+    pub keys: Option<String>,
+}
+
+impl Decision {
+    /// STATE details announcing the decision, e.g.
+    /// "Auto-approved by policy 'git': Bash: git status"
+    pub fn describe(&self, summary: &str) -> String {
+        let verb = match self.action {
+            Action::Approve => "Auto-approved",
+            Action::Escalate => "Escalated",
+            Action::Deny => "Denied",
+        };
+        format!("{} by policy '{}': {}", verb, self.rule, summary)
+    }
+}
+
+/// Rules tried in order; the first that matches decides, and a dialog no
+/// rule matches is left to the user
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    rules: Vec<PolicyRule>,
+    /// Where relative paths in rules and dialogs start from
+    base: PathBuf,
+}
+
+impl Policy {
+    pub fn config_path() -> PathBuf {
+        crate::paths::boop_dir().join("policy.toml")
+    }
+
+    pub fn log_path() -> PathBuf {
+        crate::paths::boop_dir().join("policy.log")
+    }
+
+    /// Rules from `~/.boop/policy.toml`, relative to the current
+    /// directory; none if it doesn't exist
+    pub fn load() -> Result<Self> {
+        let path = Self::config_path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load_from(&path, &std::env::current_dir()?)
+    }
+
+    pub fn load_from(path: &Path, base: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::from_toml(&contents, base).map_err(|e| BoopError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn from_toml(contents: &str, base: &Path) -> Result<Self> {
+        let file: PolicyFile = toml::from_str(contents).map_err(|e| BoopError::Config(e.to_string()))?;
+
+        let mut rules = Vec::with_capacity(file.rule.len());
+        for (i, config) in file.rule.into_iter().enumerate() {
+            let name = config.name.unwrap_or_else(|| format!("rule #{}", i + 1));
+            let action = config.action.parse().map_err(|_| {
+                BoopError::Config(format!(
+                    "rule '{}': unknown action '{}' (use approve, escalate or deny)",
+                    name, config.action
+                ))
+            })?;
+            let command = config
+                .command
+                .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+                .transpose()
+                .map_err(|e| BoopError::Config(format!("rule '{}' has an invalid command pattern: {}", name, e)))?;
+            rules.push(PolicyRule {
+                name,
+                tool: config.tool,
+                command,
+                path: config.path.map(|path| normalize(base, &path)),
+                action,
+                keys: config.keys,
+                allow_shell: config.allow_shell,
+            });
+        }
+
+        Ok(Self {
+            rules,
+            base: base.to_path_buf(),
+        })
+    }
+
+    /// The first matching rule's decision. An answer needs keys: the
+    /// rule's own, or else the first choice to approve and the last to
+    /// deny; without them the dialog is escalated.
+    pub fn decide(&self, request: &ApprovalRequest) -> Option<Decision> {
+        let rule = self.rules.iter().find(|rule| rule.matches(request, &self.base))?;
+        let keys = match rule.action {
+            Action::Approve => rule.keys.clone().or_else(|| request.keys.first().cloned()),
+            Action::Deny => rule.keys.clone().or_else(|| request.keys.last().cloned()),
+            Action::Escalate => None,
+        };
+        Some(Decision {
+            rule: rule.name.clone(),
+            action: if keys.is_some() { rule.action } else { Action::Escalate },
+            keys,
+        })
+    }
+}
+
+/// One line of `~/.boop/policy.log`
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    timestamp: u64,
+    session_id: &'a str,
+    project_name: &'a str,
+    rule: &'a str,
+    action: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keys: Option<&'a str>,
+    request: &'a ApprovalRequest,
+}
+
+/// Append a decision to `~/.boop/policy.log`
+pub fn log(session_id: &str, project_name: &str, request: &ApprovalRequest, decision: &Decision) -> Result<()> {
+    let entry = LogEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        session_id,
+        project_name,
+        rule: &decision.rule,
+        action: decision.action.as_str(),
+        keys: decision.keys.as_deref(),
+        request,
+    };
+
+    let path = Policy::log_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry).unwrap())?;
+    Ok(())
+}
+
+fn has_metacharacters(command: &str) -> bool {
+    SHELL_METACHARACTERS.iter().any(|metacharacter| command.contains(metacharacter))
+}
+
+/// `path` made absolute against `base`, with `~/` and `..` resolved
+/// without touching the filesystem, so "src/../../etc" is not in "src"
+fn normalize(base: &Path, path: &str) -> PathBuf {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(path),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bash(command: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            tool: Some("Bash".to_string()),
+            command: Some(command.to_string()),
+            choices: vec!["Yes".to_string(), "Yes, always".to_string(), "No".to_string()],
+            keys: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            ..ApprovalRequest::default()
+        }
+    }
+
+    fn edit(path: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            tool: Some("Edit".to_string()),
+            path: Some(path.to_string()),
+            keys: vec!["1".to_string(), "2".to_string()],
+            ..ApprovalRequest::default()
+        }
+    }
+
+    const POLICY: &str = r#"
+        [[rule]]
+        name = "no force pushes"
+        command = "git push.*(-f|--force).*"
+        action = "deny"
+        keys = "\u001b"
+
+        [[rule]]
+        name = "git read-only"
+        tool = "bash"
+        command = 'git (status|diff|log)( [-\w./]+)*'
+        action = "approve"
+
+        [[rule]]
+        name = "edits in src"
+        tool = "Edit"
+        path = "src"
+        action = "approve"
+
+        [[rule]]
+        name = "everything else"
+        action = "escalate"
+    "#;
+
+    #[test]
+    fn test_first_matching_rule_decides() {
+        let policy = Policy::from_toml(POLICY, Path::new("/repo")).unwrap();
+
+        let decision = policy.decide(&bash("git status --short")).unwrap();
+        assert_eq!(decision.rule, "git read-only");
+        assert_eq!(decision.action, Action::Approve);
+        assert_eq!(decision.keys.as_deref(), Some("1"));
+        assert_eq!(
+            decision.describe("Bash: git status --short"),
+            "Auto-approved by policy 'git read-only': Bash: git status --short"
+        );
+
+        let decision = policy.decide(&bash("git push --force origin main")).unwrap();
+        assert_eq!(decision.action, Action::Deny);
+        assert_eq!(decision.keys.as_deref(), Some("\x1b"));
+
+        // The whole command has to match, not a safe-looking start
+        let decision = policy.decide(&bash("git status; rm -rf ~")).unwrap();
+        assert_eq!(decision.rule, "everything else");
+        assert_eq!(decision.keys, None);
+        assert!(decision.describe("Bash").starts_with("Escalated by policy 'everything else'"));
+        assert_eq!(policy.decide(&bash("git status\n| sh")).unwrap().rule, "everything else");
+    }
+
+    #[test]
+    fn test_approve_refuses_shell_metacharacters() {
+        let policy = Policy::from_toml(
+            r#"
+            [[rule]]
+            name = "any git"
+            command = "git .*"
+            action = "approve"
+
+            [[rule]]
+            name = "everything else"
+            action = "escalate"
+            "#,
+            Path::new("/repo"),
+        )
+        .unwrap();
+        assert_eq!(policy.decide(&bash("git log --oneline")).unwrap().rule, "any git");
+        for command in [
+            "git log && rm -rf ~",
+            "git log | sh",
+            "git log $(rm -rf ~)",
+            "git log `rm -rf ~`",
+            "git log; rm -rf ~",
+            "git log > ~/.bashrc",
+            "git log\nrm -rf ~",
+        ] {
+            assert_eq!(policy.decide(&bash(command)).unwrap().rule, "everything else", "{}", command);
+        }
+
+        // Unless the rule says so; denying never needed to be careful
+        let policy = Policy::from_toml(
+            "[[rule]]\ncommand = \"git log.*\"\naction = \"approve\"\nallow_shell = true",
+            Path::new("/"),
+        )
+        .unwrap();
+        assert_eq!(policy.decide(&bash("git log | head")).unwrap().action, Action::Approve);
+        let policy = Policy::from_toml(POLICY, Path::new("/repo")).unwrap();
+        let decision = policy.decide(&bash("git push --force && echo done")).unwrap();
+        assert_eq!(decision.action, Action::Deny);
+    }
+
+    #[test]
+    fn test_approve_refuses_unclear_commands() {
+        let policy = Policy::from_toml(POLICY, Path::new("/repo")).unwrap();
+        let screen = "\
+│ Bash command                                 │
+│                                              │
+│   git log --oneline --graph --decorate --all │
+│   --stat                                     │
+│                                              │
+│ Do you want to proceed?                      │
+│ ❯ 1. Yes                                     │";
+        let request = ApprovalRequest::parse(screen).unwrap();
+        assert_eq!(request.command.as_deref(), Some("git log --oneline --graph --decorate --all\n--stat"));
+        assert!(request.unclear_command);
+        assert_eq!(policy.decide(&request).unwrap().rule, "everything else");
+
+        let request = ApprovalRequest::parse("Would you like to run the following command?\n$ git status\n--short\n› 1. Yes").unwrap();
+        assert_eq!(request.command.as_deref(), Some("git status\n--short"));
+        assert_eq!(policy.decide(&request).unwrap().rule, "everything else");
+
+        let request = ApprovalRequest::parse("Would you like to run the following command?\n$ git status\n\n› 1. Yes").unwrap();
+        assert_eq!(policy.decide(&request).unwrap().rule, "git read-only");
+    }
+
+    #[test]
+    fn test_path_prefix() {
+        let policy = Policy::from_toml(POLICY, Path::new("/repo")).unwrap();
+        assert_eq!(policy.decide(&edit("src/main.rs")).unwrap().rule, "edits in src");
+        assert_eq!(policy.decide(&edit("/repo/src/lib.rs")).unwrap().rule, "edits in src");
+        assert_eq!(policy.decide(&edit("src/../../etc/passwd")).unwrap().rule, "everything else");
+        assert_eq!(policy.decide(&edit("/repo/src-old/lib.rs")).unwrap().rule, "everything else");
+        assert_eq!(policy.decide(&edit("README.md")).unwrap().rule, "everything else");
+    }
+
+    #[test]
+    fn test_no_rules_or_no_keys() {
+        assert!(Policy::default().decide(&bash("ls")).is_none());
+
+        // A prompt whose keys weren't recognised can't be answered
+        let policy = Policy::from_toml("[[rule]]\naction = \"approve\"", Path::new("/")).unwrap();
+        let decision = policy.decide(&ApprovalRequest::default()).unwrap();
+        assert_eq!(decision.action, Action::Escalate);
+        assert_eq!(decision.keys, None);
+        assert_eq!(decision.describe("Prompt"), "Escalated by policy 'rule #1': Prompt");
+    }
+
+    #[test]
+    fn test_invalid_policy() {
+        let err = Policy::from_toml("[[rule]]\naction = \"allow\"", Path::new("/")).err().unwrap();
+        assert!(err.to_string().contains("unknown action 'allow'"));
+
+        let err = Policy::from_toml("[[rule]]\ncommand = \"(\"\naction = \"deny\"", Path::new("/"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("rule 'rule #1' has an invalid command pattern"));
+
+        assert!(Policy::from_toml("[[rule]]\naction = \"deny\"\ncmd = \"ls\"", Path::new("/")).is_err());
+    }
+}
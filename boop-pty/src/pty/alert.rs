@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+/// How to get the outer terminal's attention when a session needs the user.
+/// Works over SSH, where the app's socket isn't reachable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalAlert {
+    /// `OSC 9`: iTerm2, kitty, WezTerm, Windows Terminal
+    Osc9,
+    /// `OSC 777;notify`: foot, rxvt-unicode, WezTerm, kitty
+    Osc777,
+    /// Plain BEL; every terminal does something with it
+    Bell,
+}
+
+impl TerminalAlert {
+    pub fn names() -> &'static [&'static str] {
+        &["osc9", "osc777", "bell"]
+    }
+
+    /// The bytes to write to the outer terminal. Inside tmux, OSC sequences
+    /// are wrapped so tmux passes them on (needs `allow-passthrough on`).
+    pub fn sequence(self, title: &str, body: &str, in_tmux: bool) -> Vec<u8> {
+        let osc = match self {
+            TerminalAlert::Osc9 => format!("\x1b]9;{}: {}\x07", sanitize(title), sanitize(body)),
+            // Fields are ';'-separated, so the title can't contain one
+            TerminalAlert::Osc777 => format!(
+                "\x1b]777;notify;{};{}\x07",
+                sanitize(title).replace(';', ","),
+                sanitize(body)
+            ),
+            TerminalAlert::Bell => return b"\x07".to_vec(),
+        };
+
+        if in_tmux {
+            // DCS passthrough; ESCs inside are doubled
+            format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b")).into_bytes()
+        } else {
+            osc.into_bytes()
+        }
+    }
+}
+
+impl FromStr for TerminalAlert {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "osc9" => Ok(TerminalAlert::Osc9),
+            "osc777" => Ok(TerminalAlert::Osc777),
+            "bell" => Ok(TerminalAlert::Bell),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Control characters would end the sequence early or inject others
+fn sanitize(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(
+            TerminalAlert::Osc9.sequence("boop", "Claude is waiting for approval", false),
+            b"\x1b]9;boop: Claude is waiting for approval\x07"
+        );
+        assert_eq!(
+            TerminalAlert::Osc777.sequence("a;b", "Claude finished\x07\x1b]0;evil", false),
+            b"\x1b]777;notify;a,b;Claude finished]0;evil\x07"
+        );
+        assert_eq!(TerminalAlert::Bell.sequence("boop", "x", true), b"\x07");
+        assert_eq!(
+            TerminalAlert::Osc9.sequence("boop", "done", true),
+            b"\x1bPtmux;\x1b\x1b]9;boop: done\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("OSC9".parse(), Ok(TerminalAlert::Osc9));
+        assert_eq!("bell".parse(), Ok(TerminalAlert::Bell));
+        assert!("beep".parse::<TerminalAlert>().is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+/// Reminders about an approval nobody has answered, due at set times after
+/// it was reported. Typing or a state change stops them.
+#[derive(Debug, Default)]
+pub struct Escalation {
+    /// Ascending
+    after: Vec<Duration>,
+    waiting_since: Option<Instant>,
+    sent: usize,
+}
+
+impl Escalation {
+    pub fn new(mut after: Vec<Duration>) -> Self {
+        after.sort();
+        Self {
+            after,
+            waiting_since: None,
+            sent: 0,
+        }
+    }
+
+    pub fn start(&mut self, now: Instant) {
+        self.waiting_since = Some(now);
+        self.sent = 0;
+    }
+
+    pub fn stop(&mut self) {
+        self.waiting_since = None;
+    }
+
+    /// The reminder due by `now`, if any: its level, counting from 1, and
+    /// seconds waited. Reminders missed while asleep are folded into one.
+    pub fn poll(&mut self, now: Instant) -> Option<(u32, u64)> {
+        let waited = now.duration_since(self.waiting_since?);
+        let due = self.after.iter().take_while(|after| **after <= waited).count();
+        if due <= self.sent {
+            return None;
+        }
+        self.sent = due;
+        Some((due as u32, waited.as_secs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_reminders_at_each_interval() {
+        let mut escalation = Escalation::new(vec![10 * MINUTE, 2 * MINUTE, 30 * MINUTE]);
+        let start = Instant::now();
+        assert_eq!(escalation.poll(start + 5 * MINUTE), None);
+
+        escalation.start(start);
+        assert_eq!(escalation.poll(start + MINUTE), None);
+        assert_eq!(escalation.poll(start + 2 * MINUTE), Some((1, 120)));
+        assert_eq!(escalation.poll(start + 3 * MINUTE), None);
+        // Asleep through the 10 minute reminder
+        assert_eq!(escalation.poll(start + 31 * MINUTE), Some((3, 1860)));
+        assert_eq!(escalation.poll(start + 60 * MINUTE), None);
+
+        // Answered: nothing more until the next approval
+        escalation.start(start);
+        escalation.stop();
+        assert_eq!(escalation.poll(start + 5 * MINUTE), None);
+
+        assert_eq!(Escalation::default().poll(start), None);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::ipc::Command;
+use crate::pty::viewers::Viewer;
+
+/// Inputs to the main loop in `PtyHandler::run`, produced by helper threads
+pub enum Event {
+    /// A chunk read from the PTY master
+    Output(Vec<u8>),
+    /// A chunk typed by the user, already forwarded to the PTY
+    Input(Vec<u8>),
+    /// The outer terminal's tab came to the front (true) or went behind
+    Focus(bool),
+    /// A RESPOND or INTERRUPT from the app, to be written to the PTY
+    Command(Command),
+    /// The outer terminal was resized to (cols, rows)
+    Resize(u16, u16),
+    /// A terminal attached to or started watching the session socket
+    Attach(Viewer),
+    /// Keys typed in an attached terminal, to be written to the PTY
+    Keys(Vec<u8>),
+    /// An attached terminal was resized to (cols, rows); the PTY follows
+    ViewerResize(u16, u16),
+    /// The PTY master hit EOF or failed; the child is gone
+    Eof,
+}
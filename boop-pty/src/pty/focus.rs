@@ -0,0 +1,196 @@
+//! Focus reporting: with `CSI ?1004h` on, the outer terminal sends
+//! `ESC [ I` when its tab comes to the front and `ESC [ O` when it goes to
+//! the back, mixed in with whatever is typed
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const FOCUS_IN: &[u8] = b"\x1b[I";
+const FOCUS_OUT: &[u8] = b"\x1b[O";
+// What both start with
+const REPORT_PREFIX: &[u8] = b"\x1b[";
+
+// Longest unfinished mode change kept between chunks of output
+const MAX_PARTIAL: usize = 32;
+
+/// Picks focus reports out of stdin, including ones split across reads
+#[derive(Default)]
+pub struct FocusReports {
+    /// The start of what may be a report, at the end of the last read
+    partial: Vec<u8>,
+}
+
+impl FocusReports {
+    /// Take the focus reports out of a chunk of stdin, or leave them in
+    /// with `keep`; returns what is left for the tool, and the last focus
+    /// reported, if any. A report cut off at the end waits for the next
+    /// chunk, or for `flush`.
+    pub fn take(&mut self, keys: &[u8], keep: bool) -> (Vec<u8>, Option<bool>) {
+        let mut data = std::mem::take(&mut self.partial);
+        data.extend_from_slice(keys);
+
+        let mut rest = Vec::with_capacity(data.len());
+        let mut focused = None;
+        let mut i = 0;
+        while i < data.len() {
+            let report = [(FOCUS_IN, true), (FOCUS_OUT, false)]
+                .into_iter()
+                .find(|(report, _)| data[i..].starts_with(report));
+            if let Some((report, is_in)) = report {
+                focused = Some(is_in);
+                if keep {
+                    rest.extend_from_slice(report);
+                }
+                i += report.len();
+            } else if data.len() - i < REPORT_PREFIX.len() + 1 && REPORT_PREFIX.starts_with(&data[i..]) {
+                self.partial = data[i..].to_vec();
+                break;
+            } else {
+                rest.push(data[i]);
+                i += 1;
+            }
+        }
+        (rest, focused)
+    }
+
+    /// Whether a read ended partway into what may be a report
+    pub fn is_pending(&self) -> bool {
+        !self.partial.is_empty()
+    }
+
+    /// Nothing more came: what was held back was keys after all, e.g. Escape
+    pub fn flush(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.partial)
+    }
+}
+
+/// Whether the tool turned on focus reporting for itself, in which case
+/// the reports are passed through to it, followed from the mode changes
+/// in its output
+#[derive(Default)]
+pub struct ChildFocus {
+    /// Shared with the thread reading stdin
+    wants: Arc<AtomicBool>,
+    /// An escape sequence cut off at the end of the last chunk
+    partial: Vec<u8>,
+}
+
+impl ChildFocus {
+    pub fn wants(&self) -> Arc<AtomicBool> {
+        self.wants.clone()
+    }
+
+    /// Follow `CSI ?1004h` and `CSI ?1004l` in a chunk of output. True if
+    /// the tool turned focus reporting off, which turns it off in the
+    /// outer terminal too.
+    pub fn observe(&mut self, output: &[u8]) -> bool {
+        let mut data = std::mem::take(&mut self.partial);
+        data.extend_from_slice(output);
+
+        let mut turned_off = false;
+        let mut i = 0;
+        while let Some(start) = data[i..].iter().position(|&b| b == 0x1b) {
+            let sequence = &data[i + start..];
+            match private_mode(sequence) {
+                Mode::Incomplete => {
+                    if sequence.len() <= MAX_PARTIAL {
+                        self.partial = sequence.to_vec();
+                    }
+                    break;
+                }
+                Mode::Changed { params, set, len } => {
+                    if params.split(|&b| b == b';').any(|param| param == b"1004") {
+                        self.wants.store(set, Ordering::Relaxed);
+                        turned_off |= !set;
+                    }
+                    i += start + len;
+                }
+                Mode::Other => i += start + 1,
+            }
+        }
+        turned_off
+    }
+}
+
+enum Mode<'a> {
+    /// `CSI ? <params> h` or `l`, `len` bytes long
+    Changed { params: &'a [u8], set: bool, len: usize },
+    /// Could still become one
+    Incomplete,
+    Other,
+}
+
+fn private_mode(sequence: &[u8]) -> Mode<'_> {
+    let prefix = b"\x1b[?";
+    if sequence.len() < prefix.len() {
+        return if prefix.starts_with(sequence) { Mode::Incomplete } else { Mode::Other };
+    }
+    if !sequence.starts_with(prefix) {
+        return Mode::Other;
+    }
+    let params_len = sequence[prefix.len()..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b';')
+        .count();
+    let end = prefix.len() + params_len;
+    match sequence.get(end) {
+        None => Mode::Incomplete,
+        Some(&final_byte @ (b'h' | b'l')) => Mode::Changed {
+            params: &sequence[prefix.len()..end],
+            set: final_byte == b'h',
+            len: end + 1,
+        },
+        Some(_) => Mode::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_reports() {
+        let mut reports = FocusReports::default();
+        assert_eq!(reports.take(b"\x1b[Iy\x1b[O", false), (b"y".to_vec(), Some(false)));
+        assert_eq!(reports.take(b"\x1b[O\x1b[I", false), (Vec::new(), Some(true)));
+        assert_eq!(reports.take(b"\x1b[A1\r", false), (b"\x1b[A1\r".to_vec(), None));
+        assert_eq!(reports.take(b"y\x1b[O", true), (b"y\x1b[O".to_vec(), Some(false)));
+        assert!(!reports.is_pending());
+    }
+
+    #[test]
+    fn test_report_split_across_reads() {
+        let mut reports = FocusReports::default();
+        assert_eq!(reports.take(b"ab\x1b", false), (b"ab".to_vec(), None));
+        assert!(reports.is_pending());
+        assert_eq!(reports.take(b"[", false), (Vec::new(), None));
+        assert_eq!(reports.take(b"Oc", false), (b"c".to_vec(), Some(false)));
+
+        // Not a report after all
+        assert_eq!(reports.take(b"\x1b[", false), (Vec::new(), None));
+        assert_eq!(reports.take(b"A", false), (b"\x1b[A".to_vec(), None));
+
+        // Escape on its own is let through once nothing follows it
+        assert_eq!(reports.take(b"\x1b", false), (Vec::new(), None));
+        assert_eq!(reports.flush(), b"\x1b");
+        assert!(!reports.is_pending());
+    }
+
+    #[test]
+    fn test_child_focus_mode() {
+        let mut child = ChildFocus::default();
+        let wants = child.wants();
+
+        assert!(!child.observe(b"hello \x1b[?1049h\x1b[?2004h"));
+        assert!(!wants.load(Ordering::Relaxed));
+
+        // Cut between chunks, and combined with another mode
+        assert!(!child.observe(b"text \x1b[?10"));
+        assert!(!child.observe(b"04;2004h more"));
+        assert!(wants.load(Ordering::Relaxed));
+
+        assert!(child.observe(b"\x1b[?1004l"));
+        assert!(!wants.load(Ordering::Relaxed));
+        assert!(!child.observe(b"\x1b[?10041l\x1b[1004l"));
+    }
+}
@@ -1,43 +1,162 @@
-use crate::detector::{SessionState, StateDetector};
+use crate::attach;
+use crate::audit::{self, Approval, Source};
+use crate::detector::{SessionState, Stall, StateDetector, Transition};
 use crate::error::{BoopError, Result};
-use crate::ipc::{IpcClient, Message};
+use crate::ipc::{Command, IpcClient, Message};
+use crate::notify::{self, Fallback, Notification, NotificationKind};
+use crate::policy::{self, Policy};
+use crate::pty::alert::TerminalAlert;
+use crate::pty::escalation::Escalation;
+use crate::pty::input::is_terminal_report;
+use crate::pty::title::TitleBadge;
+use crate::pty::event::Event;
+use crate::pty::focus::{ChildFocus, FocusReports};
 use crate::pty::resize::{get_terminal_size, set_terminal_size};
+use crate::pty::viewers::{ViewerListener, Viewers};
+use crate::recording::Recorder;
+use crate::tmux::TmuxPane;
 use mio::unix::SourceFd;
 use mio::{Events, Interest, Poll, Token};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use signal_hook::consts::signal::SIGWINCH;
 use signal_hook::iterator::Signals;
-use std::io::{Read, Write};
-use std::os::fd::AsRawFd;
+use std::io::{Read, StdoutLock, Write};
+use std::os::fd::{AsRawFd, RawFd};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const STDIN_TOKEN: Token = Token(0);
 
+// Upper bound on how long the main loop waits without re-evaluating state
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+// How long stdin may pause partway into a focus report before what was
+// read is passed on as keys; a lone Escape waits this long
+const FOCUS_REPORT_WAIT: Duration = Duration::from_millis(20);
+
+/// Opt-in behaviour for a wrapped session
+#[derive(Debug, Clone, Default)]
+pub struct HandlerOptions {
+    /// Write the session to `~/.boop/recordings/<session_id>.cast`
+    pub record: bool,
+    /// Alert the outer terminal on approval and idle
+    pub alert: Option<TerminalAlert>,
+    /// Show the session state in the outer terminal's title
+    pub title: bool,
+    /// Run with no terminal of our own (stdin is /dev/null): output goes
+    /// only to terminals attached through the session socket
+    pub detached: bool,
+    /// Permission dialogs to answer without asking
+    pub policy: Policy,
+    /// Log every permission dialog and its answer to `~/.boop/audit`
+    pub audit: bool,
+    /// Remind the user of an unanswered approval this long after it was
+    /// reported, e.g. 2, 10 and 30 minutes
+    pub escalate_after: Vec<Duration>,
+    /// Track whether the outer terminal's tab is in front, and don't
+    /// notify while it is
+    pub focus: bool,
+}
+
 pub struct PtyHandler {
     session_id: String,
     tool: String,
     project_name: String,
     ipc: IpcClient,
+    /// Notifies directly while nothing is listening on the socket
+    fallback: Fallback,
     detector: StateDetector,
+    options: HandlerOptions,
+    recorder: Option<Recorder>,
+    title: Option<TitleBadge>,
+    tmux: Option<TmuxPane>,
+    /// Terminals attached through the session socket
+    viewers: Viewers,
+    /// The dialog on screen, while auditing
+    approval: Option<Approval>,
+    escalation: Escalation,
+    /// The approval or idle prompt the user was last told about, and when,
+    /// until they start typing
+    pending_ack: Option<(SessionState, Instant)>,
+    /// The STATE last sent for a state change, to send again if the user
+    /// looks away from a prompt without answering it
+    reported: Option<Message>,
+    /// Whether the tool wants focus reports itself, while tracking focus
+    child_focus: Option<ChildFocus>,
+    /// Last focus the outer terminal reported
+    focused: Option<bool>,
 }
 
 impl PtyHandler {
-    pub fn new(session_id: String, tool: String, project_name: String) -> Self {
+    pub fn new(
+        session_id: String,
+        tool: String,
+        project_name: String,
+        detector: StateDetector,
+        options: HandlerOptions,
+    ) -> Self {
+        let escalation = Escalation::new(options.escalate_after.clone());
         Self {
             session_id,
             tool,
             project_name,
             ipc: IpcClient::new(),
-            detector: StateDetector::new(),
+            fallback: Fallback::new(notify::configured()),
+            detector,
+            options,
+            recorder: None,
+            title: None,
+            tmux: None,
+            viewers: Viewers::default(),
+            approval: None,
+            escalation,
+            pending_ack: None,
+            reported: None,
+            child_focus: None,
+            focused: None,
         }
     }
 
     pub fn run(&mut self, args: &[String]) -> Result<i32> {
-        // Get initial terminal size
+        // Get initial terminal size; a detached session takes the size of
+        // whoever attaches
         let (cols, rows) = get_terminal_size().unwrap_or((80, 24));
+        self.detector.resize(rows, cols);
+        self.viewers.resize(cols, rows);
+
+        // Recording is best-effort; a failure must not stop the session
+        if self.options.record {
+            let path = Recorder::path_for(&self.session_id);
+            match Recorder::create(&path, &self.tool, cols, rows) {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(e) => eprintln!("boop-pty: not recording to {}: {}", path.display(), e),
+            }
+        }
+
+        // Helper threads and the app's commands all feed the main loop
+        let (event_tx, event_rx) = mpsc::channel::<Event>();
+        let command_tx = event_tx.clone();
+        self.ipc.on_command(move |command| {
+            let _ = command_tx.send(Event::Command(command));
+        });
+
+        // Other terminals can watch any session. Without a terminal of its
+        // own, the session socket is the only way in; otherwise it is a
+        // nicety, never worth failing over.
+        let detached = self.options.detached;
+        let path = attach::socket_path(&self.session_id);
+        let listener = match ViewerListener::bind(&path, detached, event_tx.clone()) {
+            Ok(listener) => Some(listener),
+            Err(e) if detached => {
+                return Err(BoopError::Pty(format!("Cannot listen on {}: {}", path.display(), e)));
+            }
+            Err(e) => {
+                eprintln!("boop-pty: session can't be watched: {}", e);
+                None
+            }
+        };
 
         // Create PTY
         let pty_system = native_pty_system();
@@ -71,19 +190,27 @@ impl PtyHandler {
         drop(pair.slave);
 
         // Get master reader/writer
-        let mut master_reader = pair.master.try_clone_reader()
-            .map_err(|e| BoopError::Pty(e.to_string()))?;
-        let mut master_writer = pair.master.take_writer()
+        let master_reader = pair.master.try_clone_reader()
             .map_err(|e| BoopError::Pty(e.to_string()))?;
+        // Shared by stdin forwarding, commands from the app and attached terminals
+        let master_writer = Arc::new(Mutex::new(
+            pair.master.take_writer()
+                .map_err(|e| BoopError::Pty(e.to_string()))?,
+        ));
 
         // Send START message
         let pid = std::process::id();
-        let start_msg = Message::start(&self.session_id, &self.tool, &self.project_name, pid);
-        let _ = self.ipc.send(&start_msg);
+        self.tmux = TmuxPane::current();
+        let start_msg =
+            Message::start_with_tmux(&self.session_id, &self.tool, &self.project_name, pid, self.tmux.clone());
+        self.send(&start_msg);
 
         // Send initial WORKING state
         let state_msg = Message::state(&self.session_id, SessionState::Working, "Starting...");
-        let _ = self.ipc.send(&state_msg);
+        self.send(&state_msg);
+        if let Some(tmux) = &self.tmux {
+            tmux.set_state(SessionState::Working);
+        }
 
         // Set up termination flag
         let running = Arc::new(AtomicBool::new(true));
@@ -92,6 +219,7 @@ impl PtyHandler {
         // Set up SIGWINCH handler for terminal resize
         let master_fd_opt = pair.master.as_raw_fd();
         let running_sigwinch = running.clone();
+        let resize_tx = event_tx.clone();
         thread::spawn(move || {
             if let Ok(mut signals) = Signals::new([SIGWINCH]) {
                 for _ in signals.forever() {
@@ -102,6 +230,8 @@ impl PtyHandler {
                         if let Some(fd) = master_fd_opt {
                             let _ = set_terminal_size(fd, cols, rows);
                         }
+                        // Detector screen is resized on the main thread
+                        let _ = resize_tx.send(Event::Resize(cols, rows));
                     }
                 }
             }
@@ -111,8 +241,20 @@ impl PtyHandler {
         let stdin = std::io::stdin();
         crate::terminal::set_raw_mode();
 
+        // Focus reports arrive on stdin; the tool only sees them if it asked
+        let child_focus = ChildFocus::default();
+        let child_wants_focus = child_focus.wants();
+        let tracking_focus =
+            self.options.focus && !detached && nix::unistd::isatty(stdin.as_raw_fd()).unwrap_or(false);
+        if tracking_focus {
+            crate::terminal::enable_focus_reporting();
+            self.child_focus = Some(child_focus);
+        }
+
         // Thread to read from stdin and write to PTY using poll for non-blocking
         let running_stdin = running.clone();
+        let input_tx = event_tx.clone();
+        let stdin_writer = master_writer.clone();
         let stdin_handle = thread::spawn(move || {
             let stdin_fd = std::io::stdin().as_raw_fd();
 
@@ -132,22 +274,47 @@ impl PtyHandler {
 
             let mut stdin = stdin.lock();
             let mut buf = [0u8; 1024];
+            let mut reports = FocusReports::default();
+
+            // Queued before the tool can answer, so its output is never
+            // seen ahead of the keys that caused it
+            let forward = |keys: &[u8]| {
+                let _ = input_tx.send(Event::Input(keys.to_vec()));
+                write_keys(&stdin_writer, keys).is_ok()
+            };
 
             while running_stdin.load(Ordering::Relaxed) {
-                // Poll with 100ms timeout to allow checking running flag
-                if poll.poll(&mut events, Some(Duration::from_millis(100))).is_err() {
+                // Poll with 100ms timeout to allow checking running flag, or
+                // less while a focus report may be only partly read
+                let timeout = if reports.is_pending() { FOCUS_REPORT_WAIT } else { Duration::from_millis(100) };
+                if poll.poll(&mut events, Some(timeout)).is_err() {
                     break;
                 }
 
+                if events.is_empty() {
+                    let keys = reports.flush();
+                    if !keys.is_empty() && !forward(&keys) {
+                        return;
+                    }
+                    continue;
+                }
+
                 for event in events.iter() {
                     if event.token() == STDIN_TOKEN {
                         match stdin.read(&mut buf) {
                             Ok(0) => return, // EOF
                             Ok(n) => {
-                                if master_writer.write_all(&buf[..n]).is_err() {
+                                let (keys, focused) = if tracking_focus {
+                                    reports.take(&buf[..n], child_wants_focus.load(Ordering::Relaxed))
+                                } else {
+                                    (buf[..n].to_vec(), None)
+                                };
+                                if let Some(focused) = focused {
+                                    let _ = input_tx.send(Event::Focus(focused));
+                                }
+                                if !keys.is_empty() && !forward(&keys) {
                                     return;
                                 }
-                                let _ = master_writer.flush();
                             }
                             Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                                 continue;
@@ -162,44 +329,177 @@ impl PtyHandler {
             let _ = poll.registry().deregister(&mut source_fd);
         });
 
-        // Main thread: read from PTY and write to stdout, detect state
+        // Thread to read from the PTY so the main loop can also wake on timers
+        let output_tx = event_tx;
+        thread::spawn(move || Self::read_output(master_reader, output_tx));
+
+        // Main thread: write PTY output to stdout, detect state, commit
+        // debounced states when their timer fires
         let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-        let mut buf = [0u8; 4096];
+        let mut stdout = (!detached).then(|| stdout.lock());
         let mut last_state = SessionState::Working;
 
+        if self.options.title {
+            let title = TitleBadge::new(&self.project_name, &self.tool);
+            self.show(&mut stdout, &title.start());
+            self.title = Some(title);
+        }
+
         loop {
-            match master_reader.read(&mut buf) {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    // Write to stdout
-                    if stdout.write_all(&buf[..n]).is_err() {
+            let timeout = self
+                .detector
+                .next_deadline()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(TICK_INTERVAL)
+                .min(TICK_INTERVAL);
+
+            let transition = match event_rx.recv_timeout(timeout) {
+                Ok(Event::Output(data)) => {
+                    // Write to stdout and attached terminals
+                    if !self.show(&mut stdout, &data) {
                         break;
                     }
-                    let _ = stdout.flush();
+                    self.record(|recorder| recorder.output(&data));
+
+                    // The tool turning its focus reports off turned ours off too
+                    if self.child_focus.as_mut().is_some_and(|child| child.observe(&data)) {
+                        crate::terminal::enable_focus_reporting();
+                    }
 
                     // Process for state detection
-                    if let Some((new_state, working_duration_secs)) = self.detector.process_output(&buf[..n]) {
-                        if new_state != last_state {
-                            let details = self.detector.get_details();
-                            let state_msg = Message::state_with_duration(
-                                &self.session_id,
-                                new_state,
-                                &details,
-                                working_duration_secs,
-                            );
-                            let _ = self.ipc.send(&state_msg);
-                            last_state = new_state;
+                    let transition = self.detector.process_output(&data);
+
+                    // The tool set its own title over the badge; put it back
+                    let sequence = self
+                        .title
+                        .as_mut()
+                        .and_then(|title| title.child_output(&data, self.detector.terminal_title()));
+                    if let Some(sequence) = sequence {
+                        self.show(&mut stdout, &sequence);
+                    }
+                    transition
+                }
+                Ok(Event::Input(data)) => {
+                    self.input(Source::Terminal, &data);
+                    None
+                }
+                Ok(Event::Focus(focused)) => {
+                    let looked_away = !focused && self.focused == Some(true);
+                    self.focused = Some(focused);
+                    // Whatever the prompt held back while they were looking
+                    // is due now
+                    if looked_away && self.pending_ack.is_some() {
+                        if let Some(message) = self.reported.clone() {
+                            self.send(&message);
                         }
                     }
+                    None
                 }
-                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(10));
+                Ok(Event::Command(command)) => {
+                    self.handle_command(command, &master_writer);
+                    None
+                }
+                Ok(Event::Resize(cols, rows)) => {
+                    self.detector.resize(rows, cols);
+                    self.viewers.resize(cols, rows);
+                    self.record(|recorder| recorder.resize(cols, rows));
+                    None
+                }
+                Ok(Event::Attach(viewer)) => {
+                    if let Some((cols, rows)) = viewer.size {
+                        self.resize_pty(master_fd_opt, cols, rows);
+                    }
+                    let mut snapshot = self.detector.screen_snapshot();
+                    if let Some(title) = &self.title {
+                        snapshot.extend(title.start());
+                    }
+                    self.viewers.add(viewer, snapshot);
+                    None
+                }
+                Ok(Event::Keys(keys)) => {
+                    if write_keys(&master_writer, &keys).is_ok() {
+                        self.input(Source::Viewer, &keys);
+                    }
+                    None
+                }
+                Ok(Event::ViewerResize(cols, rows)) => {
+                    self.resize_pty(master_fd_opt, cols, rows);
+                    None
+                }
+                Ok(Event::Eof) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => self.detector.tick(),
+            };
+            if self.options.audit {
+                self.watch_approval();
+            }
+
+            if let Some(transition) = transition {
+                if transition.state != last_state {
+                    self.record(|recorder| recorder.marker(transition.state.as_str()));
+                    if transition.state != SessionState::AwaitingApproval {
+                        self.finish_approval(transition.state.as_str());
+                    }
+                    self.escalation.stop();
+                    self.pending_ack = None;
+                    // A dialog the policy answered needs nobody's attention,
+                    // and the session carries on working
+                    let state = if self.send_state(transition, &master_writer) {
+                        self.detector.answered();
+                        self.finish_approval(SessionState::Working.as_str());
+                        SessionState::Working
+                    } else {
+                        if transition.state == SessionState::AwaitingApproval {
+                            self.escalation.start(Instant::now());
+                        }
+                        if matches!(transition.state, SessionState::AwaitingApproval | SessionState::Idle) {
+                            self.pending_ack = Some((transition.state, Instant::now()));
+                        }
+                        if let Some(alert) = self.alert(transition.state) {
+                            self.show(&mut stdout, &alert);
+                        }
+                        transition.state
+                    };
+                    self.update_tmux(state);
+                    if let Some(sequence) = self.title.as_mut().map(|title| title.set_state(state)) {
+                        self.show(&mut stdout, &sequence);
+                    }
+                    last_state = state;
+                }
+            }
+
+            match self.detector.poll_stall() {
+                Some(Stall::Started { silent_secs }) => {
+                    self.send(&Message::stalled(&self.session_id, silent_secs));
+                }
+                Some(Stall::Resumed) => {
+                    let msg = Message::state(&self.session_id, SessionState::Working, "Output resumed");
+                    self.send(&msg);
+                }
+                None => {}
+            }
+
+            // No reminders while the user is looking at the dialog; they
+            // stay due until the user looks away
+            let reminder = (self.focused != Some(true))
+                .then(|| self.escalation.poll(Instant::now()))
+                .flatten();
+            if let Some((level, waiting_secs)) = reminder {
+                self.send(&Message::escalate(&self.session_id, level, waiting_secs));
+                if let Some(alert) = self.alert(SessionState::AwaitingApproval) {
+                    self.show(&mut stdout, &alert);
                 }
-                Err(_) => break,
             }
         }
 
+        self.finish_approval("END");
+        crate::terminal::disable_focus_reporting();
+        if let Some(title) = self.title.take() {
+            self.show(&mut stdout, &title.finish());
+        }
+        if let Some(tmux) = &self.tmux {
+            tmux.clear_state();
+        }
+
         // Stop stdin thread
         running_clone.store(false, Ordering::Relaxed);
 
@@ -212,11 +512,215 @@ impl PtyHandler {
 
         // Send END message
         let end_msg = Message::end(&self.session_id, exit_code);
-        let _ = self.ipc.send(&end_msg);
+        self.send(&end_msg);
+
+        // No new viewers; tell the attached ones the tool is gone
+        drop(listener);
+        self.viewers.close(exit_code);
 
         // Wait for stdin thread (with timeout since poll allows it to exit)
         let _ = stdin_handle.join();
 
         Ok(exit_code)
     }
+
+    fn read_output(mut master_reader: Box<dyn Read + Send>, events: mpsc::Sender<Event>) {
+        let mut buf = [0u8; 4096];
+        loop {
+            match master_reader.read(&mut buf) {
+                Ok(0) => break, // EOF
+                Ok(n) => {
+                    if events.send(Event::Output(buf[..n].to_vec())).is_err() {
+                        return;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(_) => break,
+            }
+        }
+        let _ = events.send(Event::Eof);
+    }
+
+    /// Type a RESPOND or INTERRUPT from the app into the session, as if it
+    /// came from stdin
+    fn handle_command(&mut self, command: Command, master_writer: &Mutex<Box<dyn Write + Send>>) {
+        // Commands are addressed by session; never act on another one's
+        if command.session_id() != self.session_id {
+            return;
+        }
+        if write_keys(master_writer, command.keys()).is_ok() {
+            self.input(Source::App, command.keys());
+        }
+    }
+
+    /// Keys typed into the session, by whoever typed them. Someone is
+    /// dealing with it, so no more reminders, and the first key a person
+    /// types after a prompt acknowledges it.
+    fn input(&mut self, source: Source, keys: &[u8]) {
+        // The terminal answering the tool's queries isn't anyone typing
+        if !(source == Source::Terminal && is_terminal_report(keys)) {
+            self.escalation.stop();
+            if matches!(source, Source::Terminal | Source::Viewer) {
+                if let Some((state, since)) = self.pending_ack.take() {
+                    let response_ms = since.elapsed().as_millis() as u64;
+                    self.send(&Message::ack(&self.session_id, state, response_ms));
+                }
+            }
+        }
+        self.record(|recorder| recorder.input(keys));
+        if let Some(approval) = self.approval.as_mut() {
+            approval.input(source, keys);
+        }
+    }
+
+    /// Start auditing a dialog as soon as it is on screen, before its
+    /// debounce is over, so keys typed straight away are kept. One that
+    /// goes again before it was ever reported is logged if anything was
+    /// typed into it, and otherwise forgotten as a flicker.
+    fn watch_approval(&mut self) {
+        let showing = self.detector.state() == SessionState::AwaitingApproval
+            || self.detector.pending_state() == Some(SessionState::AwaitingApproval);
+        match &self.approval {
+            None if showing => {
+                let request = self.detector.approval_request();
+                self.approval = Some(Approval::start(&self.detector.screen_text(), request));
+            }
+            Some(approval) if !showing => {
+                if approval.is_answered() {
+                    self.finish_approval(self.detector.state().as_str());
+                } else {
+                    self.approval = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The session has moved on from the dialog being audited; log it
+    /// with what was typed
+    fn finish_approval(&mut self, outcome: &str) {
+        let Some(approval) = self.approval.take() else { return };
+        let entry = approval.finish(&self.session_id, &self.project_name, &self.tool, outcome);
+        if let Err(e) = audit::append(&entry) {
+            eprintln!("boop-pty: can't write the audit log: {}", e);
+        }
+    }
+
+    /// Apply `write` to the recorder, if any; a write error stops the
+    /// recording rather than the session
+    fn record(&mut self, write: impl FnOnce(&mut Recorder) -> std::io::Result<()>) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = write(recorder) {
+                eprintln!("boop-pty: recording stopped: {}", e);
+                self.recorder = None;
+            }
+        }
+    }
+
+    /// What to tell the user when the session starts needing them, if it does
+    fn attention(&self, state: SessionState) -> Option<Notification> {
+        let kind = match state {
+            SessionState::AwaitingApproval => NotificationKind::Approval,
+            SessionState::Idle => NotificationKind::Completed,
+            _ => return None,
+        };
+        Some(Notification::new(kind, &self.session_id, &self.tool, &self.project_name))
+    }
+
+    /// What to write to ring the outer terminal, if the session needs the
+    /// user and they aren't already looking at it
+    fn alert(&self, state: SessionState) -> Option<Vec<u8>> {
+        let alert = self.options.alert.filter(|_| self.focused != Some(true))?;
+        let notification = self.attention(state)?;
+
+        let in_tmux = std::env::var_os("TMUX").is_some();
+        Some(alert.sequence(&notification.title, &notification.message, in_tmux))
+    }
+
+    /// Write `data` to our terminal, if we have one, and to every attached
+    /// terminal. False once our terminal is gone.
+    fn show(&mut self, stdout: &mut Option<StdoutLock<'_>>, data: &[u8]) -> bool {
+        self.viewers.broadcast(data);
+        let Some(stdout) = stdout else { return true };
+        let written = stdout.write_all(data).is_ok();
+        let _ = stdout.flush();
+        written
+    }
+
+    /// An attached terminal sets the session's size; the tool redraws
+    fn resize_pty(&mut self, master_fd: Option<RawFd>, cols: u16, rows: u16) {
+        if let Some(fd) = master_fd {
+            let _ = set_terminal_size(fd, cols, rows);
+        }
+        self.detector.resize(rows, cols);
+        self.viewers.resize(cols, rows);
+        self.record(|recorder| recorder.resize(cols, rows));
+    }
+
+    /// Keep `@boop_state` current for status-line formats, and say so in
+    /// the status line when the session needs the user
+    fn update_tmux(&self, state: SessionState) {
+        let Some(tmux) = &self.tmux else { return };
+        tmux.set_state(state);
+        if let Some(notification) = self.attention(state) {
+            tmux.display(&format!("{}: {}", notification.title, notification.message));
+        }
+    }
+
+    /// Tell the app, with the terminal's focus on STATE messages; if it
+    /// isn't there, the fallback notifies instead
+    fn send(&mut self, message: &Message) {
+        let message = &message.clone().with_focus(self.focused);
+        let delivered = self.ipc.send(message).is_ok() && self.ipc.delivered();
+        self.fallback.observe(message, delivered);
+    }
+
+    /// Tell the app about a state change. A permission dialog the policy
+    /// answers is announced as working on; true if that happened.
+    fn send_state(&mut self, transition: Transition, master_writer: &Mutex<Box<dyn Write + Send>>) -> bool {
+        let details = self.detector.get_details();
+        let request = match transition.state {
+            SessionState::AwaitingApproval => self.detector.approval_request(),
+            _ => None,
+        };
+
+        let state_msg = match request {
+            Some(request) => {
+                // Prefer "Bash: git status" over whatever line was printed last
+                let mut details = request.summary().unwrap_or(details);
+                if let Some(decision) = self.options.policy.decide(&request) {
+                    if let Err(e) = policy::log(&self.session_id, &self.project_name, &request, &decision) {
+                        eprintln!("boop-pty: can't write {}: {}", Policy::log_path().display(), e);
+                    }
+                    details = decision.describe(&details);
+                    if let Some(keys) = &decision.keys {
+                        if write_keys(master_writer, keys.as_bytes()).is_ok() {
+                            self.input(Source::Policy, keys.as_bytes());
+                            self.send(&Message::state(&self.session_id, SessionState::Working, &details));
+                            return true;
+                        }
+                    }
+                }
+                Message::approval(&self.session_id, &details, transition.working_duration_secs, request)
+            }
+            None => Message::state_with_duration(
+                &self.session_id,
+                transition.state,
+                &details,
+                transition.working_duration_secs,
+            ),
+        };
+        self.send(&state_msg);
+        self.reported = Some(state_msg);
+        false
+    }
+}
+
+fn write_keys(master_writer: &Mutex<Box<dyn Write + Send>>, keys: &[u8]) -> std::io::Result<()> {
+    let mut writer = master_writer.lock().unwrap();
+    writer.write_all(keys)?;
+    let _ = writer.flush();
+    Ok(())
 }
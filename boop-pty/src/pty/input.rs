@@ -0,0 +1,67 @@
+//! Telling what the user typed apart from what their terminal sends on its
+//! own, in answer to the tool's queries
+
+/// Whether `keys` is entirely a terminal's reply to a query: a cursor
+/// position report (`CSI row ; col R`), a status report (`CSI 0 n` or
+/// `CSI 3 n`), device attributes (`CSI ? ... c` or `CSI > ... c`), a focus
+/// report, or an OSC or DCS string. `CSI 1 ; 2..16 R` is left as typing:
+/// it is also F3 with a modifier.
+pub fn is_terminal_report(keys: &[u8]) -> bool {
+    match keys {
+        [0x1b, b']' | b'P', ..] => true,
+        [0x1b, b'[', b'I' | b'O'] => true,
+        [0x1b, b'[', b'0' | b'3', b'n'] => true,
+        [0x1b, b'[', b'?' | b'>', attributes @ .., b'c'] => is_parameters(attributes),
+        [0x1b, b'[', position @ .., b'R'] => match position.split(|&b| b == b';').collect::<Vec<_>>()[..] {
+            [row, col] if is_number(row) && is_number(col) => !(row == b"1" && is_modifier(col)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Digits separated by `;`
+fn is_parameters(bytes: &[u8]) -> bool {
+    bytes.split(|&b| b == b';').all(is_number)
+}
+
+fn is_number(bytes: &[u8]) -> bool {
+    !bytes.is_empty() && bytes.iter().all(u8::is_ascii_digit)
+}
+
+/// The xterm modifier parameter of a key: 2 (Shift) to 16
+fn is_modifier(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).ok().and_then(|n| n.parse::<u8>().ok()).is_some_and(|n| (2..=16).contains(&n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_reports() {
+        assert!(is_terminal_report(b"\x1b[12;40R"));
+        assert!(is_terminal_report(b"\x1b[?62;22c"));
+        assert!(is_terminal_report(b"\x1b[>41;330;0c"));
+        assert!(is_terminal_report(b"\x1b[0n"));
+        assert!(is_terminal_report(b"\x1b[1;40R"));
+        assert!(is_terminal_report(b"\x1b[I"));
+        assert!(is_terminal_report(b"\x1b]11;rgb:0000/0000/0000\x1b\\"));
+
+        // Typing
+        assert!(!is_terminal_report(b"y"));
+        assert!(!is_terminal_report(b"\x1b[A"));
+        assert!(!is_terminal_report(b"\x1b"));
+        assert!(!is_terminal_report(b"\x1b[3~"));
+        assert!(!is_terminal_report(b"\r"));
+        assert!(!is_terminal_report(b"\x1b[1;5R"));
+        assert!(!is_terminal_report(b"\x1b[1;2R"));
+
+        // Not the shape of any reply
+        assert!(!is_terminal_report(b"\x1b[?12;40R"));
+        assert!(!is_terminal_report(b"\x1b[5n"));
+        assert!(!is_terminal_report(b"\x1b[62c"));
+        assert!(!is_terminal_report(b"\x1b[?c"));
+        assert!(!is_terminal_report(b"\x1b[8;24;80t"));
+    }
+}
@@ -1,4 +1,13 @@
+mod alert;
+mod escalation;
+mod event;
+mod focus;
 mod handler;
+mod input;
 mod resize;
+mod title;
+mod viewers;
 
-pub use handler::PtyHandler;
+pub use alert::TerminalAlert;
+pub use handler::{HandlerOptions, PtyHandler};
+pub(crate) use resize::get_terminal_size;
@@ -0,0 +1,150 @@
+use crate::detector::SessionState;
+
+/// Keeps the outer terminal's title showing the session's state, e.g.
+/// `🔔 approval · boop · claude`, so a tab that needs the user stands out.
+/// A title the tool sets for itself is kept, with the badge in front.
+pub struct TitleBadge {
+    project_name: String,
+    tool: String,
+    state: SessionState,
+    /// Last title the tool set, empty if none
+    child_title: String,
+    /// Where the last chunk of output left off in an OSC sequence
+    osc: Osc,
+}
+
+/// Progress through `ESC ] 0 ;` or `ESC ] 2 ;`, a title, and its terminator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Osc {
+    Ground,
+    Escape,
+    Bracket,
+    /// Saw the 0 or 2
+    Number,
+    Title,
+    TitleEscape,
+}
+
+impl TitleBadge {
+    pub fn new(project_name: &str, tool: &str) -> Self {
+        Self {
+            project_name: project_name.to_string(),
+            tool: tool.to_string(),
+            state: SessionState::Working,
+            child_title: String::new(),
+            osc: Osc::Ground,
+        }
+    }
+
+    /// Save the terminal's own title on its title stack, then show the badge
+    pub fn start(&self) -> Vec<u8> {
+        let mut out = b"\x1b[22;0t".to_vec();
+        out.extend(self.sequence());
+        out
+    }
+
+    pub fn set_state(&mut self, state: SessionState) -> Vec<u8> {
+        self.state = state;
+        self.sequence()
+    }
+
+    /// The tool wrote `output`, and its title is now `title`. Returns the
+    /// sequence putting the badge back if the output set a title, even the
+    /// same one again, since that replaced the badge on the way through.
+    pub fn child_output(&mut self, output: &[u8], title: &str) -> Option<Vec<u8>> {
+        self.child_title = title.to_string();
+        self.sets_title(output).then(|| self.sequence())
+    }
+
+    /// Whether `output` finishes an OSC 0 or OSC 2 title sequence,
+    /// following ones split across chunks
+    fn sets_title(&mut self, output: &[u8]) -> bool {
+        let mut finished = false;
+        for &byte in output {
+            self.osc = match (self.osc, byte) {
+                (Osc::Title, 0x07) | (Osc::TitleEscape, b'\\') => {
+                    finished = true;
+                    Osc::Ground
+                }
+                (Osc::Title | Osc::TitleEscape, 0x1b) => Osc::TitleEscape,
+                (Osc::Title, _) => Osc::Title,
+                (Osc::TitleEscape, _) => Osc::Ground,
+                (_, 0x1b) => Osc::Escape,
+                (Osc::Escape, b']') => Osc::Bracket,
+                (Osc::Bracket, b'0' | b'2') => Osc::Number,
+                (Osc::Number, b';') => Osc::Title,
+                _ => Osc::Ground,
+            };
+        }
+        finished
+    }
+
+    /// Restore the title saved by `start`
+    pub fn finish(&self) -> Vec<u8> {
+        b"\x1b[23;0t".to_vec()
+    }
+
+    pub fn text(&self) -> String {
+        let badge = match self.state {
+            SessionState::Working => "⏳",
+            SessionState::AwaitingApproval => "🔔 approval ·",
+            SessionState::Idle => "✅ idle ·",
+            SessionState::Completed => "✅ done ·",
+            SessionState::Error => "❌ error ·",
+        };
+        let name = if self.child_title.is_empty() {
+            format!("{} · {}", self.project_name, self.tool)
+        } else {
+            self.child_title.clone()
+        };
+        let title = format!("{} {}", badge, name);
+        // Control characters would end the sequence early
+        title.chars().filter(|c| !c.is_control()).collect()
+    }
+
+    fn sequence(&self) -> Vec<u8> {
+        format!("\x1b]2;{}\x07", self.text()).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge() {
+        let mut title = TitleBadge::new("boop", "claude");
+        assert_eq!(title.text(), "⏳ boop · claude");
+        assert!(title.start().starts_with(b"\x1b[22;0t\x1b]2;"));
+
+        assert_eq!(
+            title.set_state(SessionState::AwaitingApproval),
+            "\x1b]2;🔔 approval · boop · claude\x07".as_bytes()
+        );
+
+        // The tool's own title is re-applied behind the badge
+        let badge = title.child_output(b"\x1b]0;\xe2\x9c\xb3 Refactor auth\x07", "✳ Refactor auth");
+        assert_eq!(badge.unwrap(), "\x1b]2;🔔 approval · ✳ Refactor auth\x07".as_bytes());
+        assert!(title.child_output(b"plain output\r\n", "✳ Refactor auth").is_none());
+        title.set_state(SessionState::Idle);
+        assert_eq!(title.text(), "✅ idle · ✳ Refactor auth");
+    }
+
+    #[test]
+    fn test_same_title_again() {
+        let mut title = TitleBadge::new("boop", "claude");
+
+        // Setting the same title again still overwrote the badge
+        assert!(title.child_output(b"\x1b]2;claude\x07", "claude").is_some());
+        assert!(title.child_output(b"\x1b]2;claude\x1b\\", "claude").is_some());
+
+        // Split across reads, finished by the second one
+        assert!(title.child_output(b"text \x1b]", "claude").is_none());
+        assert!(title.child_output(b"2;cla", "claude").is_none());
+        assert!(title.child_output(b"ude\x07more", "claude").is_some());
+
+        // Other OSC sequences, like hyperlinks, leave the title alone
+        assert!(title.child_output(b"\x1b]8;;https://example.com\x07link\x1b]8;;\x07", "claude").is_none());
+        assert!(title.child_output(b"\x1b]1;icon\x07", "claude").is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use crate::attach::Frame;
+use crate::pty::event::Event;
+use std::fs;
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Output chunks a viewer may fall behind by before it is dropped; the
+/// session never waits for a slow viewer
+const QUEUE_CHUNKS: usize = 1024;
+
+// A client that connects but never says hello is dropped
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+// A client that stops reading is dropped rather than holding a thread
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A terminal connected to the session socket
+pub struct Viewer {
+    /// Terminal size the client attached with; `None` for a watcher, which
+    /// can't type or resize
+    pub size: Option<(u16, u16)>,
+    frames: SyncSender<Frame>,
+    writer: JoinHandle<()>,
+}
+
+/// Listens on the session socket, handing each client to the main loop as
+/// `Event::Attach`, then an attached client's keys and resizes as further
+/// events. The socket is removed on drop.
+pub struct ViewerListener {
+    path: PathBuf,
+}
+
+impl ViewerListener {
+    /// Unless `allow_attach`, clients can only watch: a session with a
+    /// terminal of its own already has someone typing and setting its size
+    pub fn bind(path: &Path, allow_attach: bool, events: mpsc::Sender<Event>) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // A socket nobody answers on is left over from a crash
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} is in use by another session", path.display()),
+                ));
+            }
+            fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        // Anyone who can connect can type into the session
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let events = events.clone();
+                thread::spawn(move || serve(stream, allow_attach, events));
+            }
+        });
+
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for ViewerListener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn serve(stream: UnixStream, allow_attach: bool, events: mpsc::Sender<Event>) {
+    let Ok(mut reader) = stream.try_clone() else { return };
+    let _ = reader.set_read_timeout(Some(HELLO_TIMEOUT));
+    let size = match Frame::read_from(&mut reader) {
+        Ok(Some(Frame::Hello { cols, rows })) if allow_attach => Some((cols, rows)),
+        Ok(Some(Frame::Watch)) => None,
+        _ => return,
+    };
+    let _ = reader.set_read_timeout(None);
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+
+    let (frames, queue) = mpsc::sync_channel(QUEUE_CHUNKS);
+    let writer = thread::spawn(move || write_frames(stream, queue));
+    let viewer = Viewer { size, frames, writer };
+    if events.send(Event::Attach(viewer)).is_err() || size.is_none() {
+        return;
+    }
+
+    while let Ok(Some(frame)) = Frame::read_from(&mut reader) {
+        let event = match frame {
+            Frame::Input(keys) => Event::Keys(keys),
+            Frame::Resize { cols, rows } => Event::ViewerResize(cols, rows),
+            _ => continue,
+        };
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+fn write_frames(mut stream: UnixStream, queue: Receiver<Frame>) {
+    for frame in queue {
+        if frame.write_to(&mut stream).is_err() {
+            break;
+        }
+    }
+    // Ends the client's session, and unblocks our reader for this client
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Everyone attached to the session. Sending never blocks: a viewer that
+/// can't keep up is disconnected rather than shown a garbled screen.
+#[derive(Default)]
+pub struct Viewers {
+    viewers: Vec<Viewer>,
+    /// The session's size, which viewers are told so they can lay out what
+    /// they're sent
+    size: (u16, u16),
+}
+
+impl Viewers {
+    /// Start `viewer` off with the session's size and `snapshot`, a redraw
+    /// of the current screen
+    pub fn add(&mut self, viewer: Viewer, snapshot: Vec<u8>) {
+        let (cols, rows) = self.size;
+        if viewer.frames.try_send(Frame::Resize { cols, rows }).is_ok()
+            && viewer.frames.try_send(Frame::Output(snapshot)).is_ok()
+        {
+            self.viewers.push(viewer);
+        }
+    }
+
+    pub fn broadcast(&mut self, data: &[u8]) {
+        self.send(|| Frame::Output(data.to_vec()));
+    }
+
+    /// The session was resized to (cols, rows)
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        self.size = (cols, rows);
+        self.send(|| Frame::Resize { cols, rows });
+    }
+
+    fn send(&mut self, frame: impl Fn() -> Frame) {
+        self.viewers.retain(|viewer| viewer.frames.try_send(frame()).is_ok());
+    }
+
+    /// Tell every viewer the tool exited, and wait until they've been told
+    pub fn close(&mut self, exit_code: i32) {
+        for viewer in self.viewers.drain(..) {
+            let _ = viewer.frames.try_send(Frame::Exit(exit_code));
+            drop(viewer.frames);
+            let _ = viewer.writer.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next_event(events: &Receiver<Event>) -> Event {
+        events.recv_timeout(Duration::from_secs(5)).expect("event")
+    }
+
+    #[test]
+    fn test_attach_and_fan_out() {
+        let path = std::env::temp_dir().join(format!("boop-viewers-{}.sock", std::process::id()));
+        let (event_tx, event_rx) = mpsc::channel();
+        let listener = ViewerListener::bind(&path, true, event_tx).unwrap();
+        assert!(ViewerListener::bind(&path, true, mpsc::channel().0).is_err());
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        Frame::Hello { cols: 100, rows: 30 }.write_to(&mut client).unwrap();
+        let Event::Attach(viewer) = next_event(&event_rx) else { panic!("expected attach") };
+        assert_eq!(viewer.size, Some((100, 30)));
+
+        let mut viewers = Viewers::default();
+        viewers.resize(120, 40);
+        viewers.add(viewer, b"screen".to_vec());
+        viewers.broadcast(b"more");
+        assert_eq!(viewers.viewers.len(), 1);
+
+        Frame::Input(b"y".to_vec()).write_to(&mut client).unwrap();
+        Frame::Resize { cols: 80, rows: 24 }.write_to(&mut client).unwrap();
+        assert!(matches!(next_event(&event_rx), Event::Keys(keys) if keys == b"y"));
+        assert!(matches!(next_event(&event_rx), Event::ViewerResize(80, 24)));
+
+        viewers.close(3);
+        let mut received = Vec::new();
+        while let Some(frame) = Frame::read_from(&mut client).unwrap() {
+            received.push(frame);
+        }
+        assert_eq!(
+            received,
+            [
+                Frame::Resize { cols: 120, rows: 40 },
+                Frame::Output(b"screen".to_vec()),
+                Frame::Output(b"more".to_vec()),
+                Frame::Exit(3)
+            ]
+        );
+
+        drop(listener);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_watch_only() {
+        let path = std::env::temp_dir().join(format!("boop-watch-{}.sock", std::process::id()));
+        let (event_tx, event_rx) = mpsc::channel();
+        let _listener = ViewerListener::bind(&path, false, event_tx).unwrap();
+
+        // Attaching is refused; watching is not, and keys from a watcher
+        // never reach the session
+        let mut attacher = UnixStream::connect(&path).unwrap();
+        Frame::Hello { cols: 100, rows: 30 }.write_to(&mut attacher).unwrap();
+        assert_eq!(Frame::read_from(&mut attacher).unwrap(), None);
+
+        let mut watcher = UnixStream::connect(&path).unwrap();
+        Frame::Watch.write_to(&mut watcher).unwrap();
+        Frame::Input(b"y".to_vec()).write_to(&mut watcher).unwrap();
+        let Event::Attach(viewer) = next_event(&event_rx) else { panic!("expected attach") };
+        assert_eq!(viewer.size, None);
+        assert!(event_rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+        let mut viewers = Viewers::default();
+        viewers.add(viewer, b"screen".to_vec());
+        viewers.close(0);
+        assert!(matches!(Frame::read_from(&mut watcher).unwrap(), Some(Frame::Resize { .. })));
+        assert_eq!(Frame::read_from(&mut watcher).unwrap(), Some(Frame::Output(b"screen".to_vec())));
+    }
+
+    #[test]
+    fn test_slow_viewer_is_dropped() {
+        let (frames, _queue) = mpsc::sync_channel(QUEUE_CHUNKS);
+        let viewer = Viewer {
+            size: None,
+            frames,
+            writer: thread::spawn(|| {}),
+        };
+        let mut viewers = Viewers::default();
+        viewers.add(viewer, Vec::new());
+
+        // Nothing drains the queue; the session carries on without it
+        for _ in 0..QUEUE_CHUNKS - 1 {
+            viewers.broadcast(b"output");
+        }
+        assert!(viewers.viewers.is_empty());
+    }
+}
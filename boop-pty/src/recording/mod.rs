@@ -0,0 +1,5 @@
+mod replay;
+mod writer;
+
+pub use replay::{Cast, Replay};
+pub use writer::Recorder;
@@ -0,0 +1,268 @@
+use crate::detector::{Clock, DetectorConfig, ManualClock, SessionState, Stall, StateDetector, Trigger};
+use crate::error::{BoopError, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct Header {
+    version: u8,
+    width: u16,
+    height: u16,
+    #[serde(default)]
+    title: String,
+}
+
+/// One `[time, code, data]` line of a `.cast` file
+#[derive(Debug, Clone, Deserialize)]
+pub struct CastEvent {
+    pub time: f64,
+    pub code: String,
+    pub data: String,
+}
+
+/// A parsed asciicast v2 recording, as written by `Recorder`
+pub struct Cast {
+    pub width: u16,
+    pub height: u16,
+    /// The wrapped tool, used to pick a detector profile
+    pub title: String,
+    pub events: Vec<CastEvent>,
+}
+
+impl Cast {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents).map_err(|e| BoopError::Recording(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut lines = contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, first) = lines
+            .next()
+            .ok_or_else(|| BoopError::Recording("empty recording".to_string()))?;
+        let header: Header = serde_json::from_str(first)
+            .map_err(|e| BoopError::Recording(format!("invalid header: {}", e)))?;
+        if header.version != 2 {
+            return Err(BoopError::Recording(format!(
+                "unsupported asciicast version {}",
+                header.version
+            )));
+        }
+
+        let events = lines
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| BoopError::Recording(format!("line {}: {}", i + 1, e)))
+            })
+            .collect::<Result<Vec<CastEvent>>>()?;
+
+        Ok(Self {
+            width: header.width,
+            height: header.height,
+            title: header.title,
+            events,
+        })
+    }
+
+    /// Transitions committed during the live session, from its "m" events
+    pub fn markers(&self) -> Vec<SessionState> {
+        self.events
+            .iter()
+            .filter(|event| event.code == "m")
+            .filter_map(|event| event.data.parse().ok())
+            .collect()
+    }
+}
+
+/// Something the detector reported during a replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    State(SessionState, Trigger),
+    Stalled { silent_secs: u64 },
+    Resumed,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::State(state, trigger) => write!(f, "{:<17}  {}", state.as_str(), trigger),
+            Change::Stalled { silent_secs } => {
+                write!(f, "{:<17}  no output for {}s", "STALLED", silent_secs)
+            }
+            Change::Resumed => write!(f, "{:<17}  output resumed", "RESUMED"),
+        }
+    }
+}
+
+/// Timeline produced by feeding a recording through a detector
+pub struct Replay {
+    /// Seconds since the recording started, and what changed
+    pub timeline: Vec<(f64, Change)>,
+}
+
+impl Replay {
+    /// Feed every event through a detector built from `config`, on a
+    /// manual clock. Debounce and silence timers fire at the recorded moment
+    /// they would have fired live, so a replay takes no longer than parsing
+    /// the file.
+    pub fn run(cast: &Cast, config: DetectorConfig) -> Self {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut detector = StateDetector::with_clock(config, Arc::new(clock.clone()));
+        detector.resize(cast.height, cast.width);
+
+        let mut replay = Self { timeline: Vec::new() };
+        let mut last_state = SessionState::Working;
+        let mut record = |detector: &mut StateDetector, state: Option<SessionState>| {
+            let time = clock.now().duration_since(start).as_secs_f64();
+
+            if let Some(state) = state.filter(|state| *state != last_state) {
+                let trigger = detector.trigger().cloned().unwrap_or(Trigger::NoMatch);
+                replay.timeline.push((time, Change::State(state, trigger)));
+                last_state = state;
+            }
+
+            match detector.poll_stall() {
+                Some(Stall::Started { silent_secs }) => {
+                    replay.timeline.push((time, Change::Stalled { silent_secs }))
+                }
+                Some(Stall::Resumed) => replay.timeline.push((time, Change::Resumed)),
+                None => {}
+            }
+        };
+
+        for event in &cast.events {
+            // Timers that would have fired before this event arrived
+            let until = start + Duration::from_secs_f64(event.time.max(0.0));
+            while let Some(deadline) = detector
+                .next_deadline()
+                .filter(|deadline| *deadline > clock.now() && *deadline <= until)
+            {
+                clock.set(deadline);
+                let transition = detector.tick();
+                record(&mut detector, transition.map(|t| t.state));
+            }
+            clock.set(until);
+
+            let transition = match event.code.as_str() {
+                "o" => detector.process_output(event.data.as_bytes()),
+                "r" => {
+                    if let Some((cols, rows)) = parse_size(&event.data) {
+                        detector.resize(rows, cols);
+                    }
+                    None
+                }
+                // Input does not drive detection, and markers are what is being checked
+                _ => None,
+            };
+            record(&mut detector, transition.map(|t| t.state));
+        }
+
+        replay
+    }
+
+    /// The committed states, in order
+    pub fn states(&self) -> Vec<SessionState> {
+        self.timeline
+            .iter()
+            .filter_map(|(_, change)| match change {
+                Change::State(state, _) => Some(*state),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// "120x40" as written by `Recorder::resize`
+fn parse_size(data: &str) -> Option<(u16, u16)> {
+    let (cols, rows) = data.split_once('x')?;
+    Some((cols.parse().ok()?, rows.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::{DetectorProfile, DetectorTiming, PatternMatcher};
+
+    fn config() -> DetectorConfig {
+        let profile = DetectorProfile::by_name("generic").unwrap();
+        DetectorConfig {
+            patterns: PatternMatcher::for_profile(profile),
+            timing: DetectorTiming {
+                debounce: Duration::from_millis(500),
+                idle_silence: Some(Duration::from_secs(5)),
+                stall_silence: Some(Duration::from_secs(60)),
+            },
+        }
+    }
+
+    const CAST: &str = r#"{"version":2,"width":80,"height":24,"timestamp":0,"title":"generic"}
+[0.1,"o","Compiling boop-pty\r\n"]
+[2.0,"o","Overwrite file? [Y/n] "]
+[2.5,"m","AWAITING_APPROVAL"]
+[4.0,"i","y"]
+[4.1,"o","y\r\nwriting\r\nchecking\r\n"]
+[5.0,"r","100x30"]
+[6.0,"o","architect> "]
+[20.0,"o","bob"]
+[20.5,"m","WORKING"]
+"#;
+
+    #[test]
+    fn test_parse_cast() {
+        let cast = Cast::parse(CAST).unwrap();
+        assert_eq!((cast.width, cast.height), (80, 24));
+        assert_eq!(cast.title, "generic");
+        assert_eq!(cast.events.len(), 9);
+        assert_eq!(cast.markers(), vec![SessionState::AwaitingApproval, SessionState::Working]);
+        assert_eq!(parse_size(&cast.events[5].data), Some((100, 30)));
+
+        assert!(Cast::parse("").is_err());
+        assert!(Cast::parse(r#"{"version":1,"width":80,"height":24}"#).is_err());
+        assert!(Cast::parse("{\"version\":2,\"width\":80,\"height\":24}\n[0.1,\"o\"]").is_err());
+    }
+
+    #[test]
+    fn test_replay_timeline() {
+        let cast = Cast::parse(CAST).unwrap();
+        let replay = Replay::run(&cast, config());
+
+        assert_eq!(
+            replay.states(),
+            vec![
+                SessionState::AwaitingApproval,
+                SessionState::Working,
+                SessionState::Idle,
+                SessionState::Working,
+            ]
+        );
+
+        // The prompt commits once its debounce passes, not at the next event
+        let (time, change) = &replay.timeline[0];
+        assert!((time - 2.5).abs() < 0.01);
+        match change {
+            Change::State(_, Trigger::Rule { name, .. }) => assert!(name.starts_with("generic:awaiting_approval")),
+            other => panic!("unexpected change: {:?}", other),
+        }
+
+        // The unknown prompt goes idle after five quiet seconds
+        let (time, change) = &replay.timeline[2];
+        assert!((time - 11.5).abs() < 0.01);
+        assert_eq!(*change, Change::State(SessionState::Idle, Trigger::Silence));
+    }
+
+    #[test]
+    fn test_replay_reports_stall() {
+        let cast = Cast::parse(
+            "{\"version\":2,\"width\":80,\"height\":24}\n[0.0,\"o\",\"Compiling\\r\\n\"]\n[90.0,\"o\",\"Finished\\r\\n\"]",
+        )
+        .unwrap();
+        let replay = Replay::run(&cast, config());
+        let changes: Vec<&Change> = replay.timeline.iter().map(|(_, change)| change).collect();
+        assert_eq!(changes, vec![&Change::Stalled { silent_secs: 60 }, &Change::Resumed]);
+    }
+}
@@ -0,0 +1,177 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// asciicast v2 header, the first line of a `.cast` file
+#[derive(Serialize)]
+struct Header {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+    title: String,
+    env: BTreeMap<String, String>,
+}
+
+/// Writes a session to an asciinema v2 `.cast` file: PTY output ("o"),
+/// stdin ("i"), resizes ("r") and detector transitions as markers ("m"),
+/// each stamped with seconds since the recording started.
+pub struct Recorder {
+    file: LineWriter<File>,
+    started: Instant,
+    output: Utf8Stream,
+    input: Utf8Stream,
+}
+
+impl Recorder {
+    pub fn recordings_dir() -> PathBuf {
+        crate::paths::boop_dir().join("recordings")
+    }
+
+    pub fn path_for(session_id: &str) -> PathBuf {
+        // Session ids are UUIDs, but never let one escape the directory
+        let name = session_id.replace(['/', '\\'], "_");
+        Self::recordings_dir().join(format!("{}.cast", name))
+    }
+
+    pub fn create(path: &Path, title: &str, cols: u16, rows: u16) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut env = BTreeMap::new();
+        for key in ["TERM", "SHELL"] {
+            if let Ok(value) = std::env::var(key) {
+                env.insert(key.to_string(), value);
+            }
+        }
+
+        let header = Header {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            title: title.to_string(),
+            env,
+        };
+
+        // Recordings hold everything typed, so only the user may read them
+        let file = OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+        let mut file = LineWriter::new(file);
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            output: Utf8Stream::default(),
+            input: Utf8Stream::default(),
+        })
+    }
+
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        let text = self.output.decode(data);
+        self.event("o", &text)
+    }
+
+    pub fn input(&mut self, data: &[u8]) -> io::Result<()> {
+        let text = self.input.decode(data);
+        self.event("i", &text)
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    pub fn marker(&mut self, label: &str) -> io::Result<()> {
+        self.event("m", label)
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        // A chunk holding only part of a character is written with the next one
+        if data.is_empty() && code != "m" {
+            return Ok(());
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let line = serde_json::to_string(&(elapsed, code, data))?;
+        writeln!(self.file, "{}", line)
+    }
+}
+
+/// Decodes a byte stream as UTF-8 across chunk boundaries, holding back an
+/// incomplete trailing sequence until the rest of it arrives
+#[derive(Default)]
+struct Utf8Stream {
+    pending: Vec<u8>,
+}
+
+impl Utf8Stream {
+    fn decode(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Invalid bytes in the middle are replaced; only a truncated
+            // sequence at the very end is held back
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_utf8_split_across_chunks() {
+        let mut stream = Utf8Stream::default();
+        let bytes = "❯ yes".as_bytes();
+        assert_eq!(stream.decode(&bytes[..2]), "");
+        assert_eq!(stream.decode(&bytes[2..]), "❯ yes");
+        assert_eq!(stream.decode(b"bad \xff byte"), "bad \u{fffd} byte");
+    }
+
+    #[test]
+    fn test_cast_file() {
+        let dir = std::env::temp_dir().join(format!("boop-cast-{}", std::process::id()));
+        let path = dir.join("test-session.cast");
+
+        let mut recorder = Recorder::create(&path, "claude", 100, 30).unwrap();
+        recorder.output(b"Do you want to proceed?\r\n").unwrap();
+        recorder.input(b"1").unwrap();
+        recorder.resize(120, 40).unwrap();
+        recorder.marker("AWAITING_APPROVAL").unwrap();
+        drop(recorder);
+
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 100);
+        assert_eq!(lines[0]["height"], 30);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "Do you want to proceed?\r\n");
+        assert_eq!(lines[2][1], "i");
+        assert_eq!(lines[3][2], "120x40");
+        assert_eq!(lines[4][1], "m");
+        assert_eq!(lines[4][2], "AWAITING_APPROVAL");
+        assert!(lines[4][0].as_f64().unwrap() >= lines[1][0].as_f64().unwrap());
+    }
+}
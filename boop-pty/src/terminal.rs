@@ -1,8 +1,10 @@
 use nix::sys::termios::{tcgetattr, tcsetattr, SetArg, Termios};
 use std::os::fd::{AsRawFd, BorrowedFd};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
 static ORIGINAL_TERMIOS: Mutex<Option<Termios>> = Mutex::new(None);
+static FOCUS_REPORTING: AtomicBool = AtomicBool::new(false);
 
 pub fn save_terminal_settings() {
     let fd = std::io::stdin().as_raw_fd();
@@ -15,6 +17,7 @@ pub fn save_terminal_settings() {
 }
 
 pub fn restore_terminal_settings() {
+    disable_focus_reporting();
     if let Ok(guard) = ORIGINAL_TERMIOS.lock() {
         if let Some(ref original) = *guard {
             let fd = std::io::stdin().as_raw_fd();
@@ -42,3 +45,24 @@ pub fn set_raw_mode() {
         }
     }
 }
+
+/// Ask the terminal to report focus changes on stdin (`CSI ?1004h`)
+pub fn enable_focus_reporting() {
+    FOCUS_REPORTING.store(true, Ordering::Relaxed);
+    write_stdout(b"\x1b[?1004h");
+}
+
+/// Stop focus reports, if `enable_focus_reporting` started them
+pub fn disable_focus_reporting() {
+    if FOCUS_REPORTING.swap(false, Ordering::Relaxed) {
+        write_stdout(b"\x1b[?1004l");
+    }
+}
+
+// Straight to the file descriptor: the main loop holds the stdout lock
+// for as long as the session runs, and signal handlers still need this
+fn write_stdout(data: &[u8]) {
+    unsafe {
+        libc::write(libc::STDOUT_FILENO, data.as_ptr().cast(), data.len());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::detector::SessionState;
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, LazyLock};
+use std::thread;
+use std::time::Duration;
+
+// Tab-separated, so session names with spaces survive
+const PANE_FORMAT: &str = "#{session_name}\t#{window_index}\t#{pane_index}\t#{pane_id}";
+
+/// Option set on the pane and its window, for status-line formats
+pub const STATE_OPTION: &str = "@boop_state";
+
+// How long a finished session waits for its last tmux commands
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum Job {
+    Run(Vec<String>),
+    /// Unset the option on a pane, and on its window unless another pane
+    /// there still has it
+    Clear(String),
+    Flush(mpsc::Sender<()>),
+}
+
+// tmux commands run one at a time, in order, on their own thread, so a
+// slow or hung server never holds up the terminal
+static WORKER: LazyLock<mpsc::Sender<Job>> = LazyLock::new(|| {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for job in rx {
+            match job {
+                Job::Run(args) => {
+                    run(&args);
+                }
+                Job::Clear(pane_id) => clear(&pane_id),
+                Job::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    tx
+});
+
+/// The tmux pane a session runs in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TmuxPane {
+    pub session: String,
+    pub window: String,
+    pub pane: String,
+    /// Unique for the server's lifetime, e.g. "%12"
+    pub pane_id: String,
+}
+
+impl TmuxPane {
+    /// The pane `boop-pty` is running in; `None` outside tmux
+    pub fn current() -> Option<Self> {
+        std::env::var_os("TMUX")?;
+        let pane_id = std::env::var("TMUX_PANE").ok()?;
+        let output = Command::new("tmux")
+            .args(["display-message", "-p", "-t", &pane_id, PANE_FORMAT])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.trim_end_matches('\n').split('\t');
+        let pane = Self {
+            session: fields.next()?.to_string(),
+            window: fields.next()?.to_string(),
+            pane: fields.next()?.to_string(),
+            pane_id: fields.next()?.to_string(),
+        };
+        fields.next().is_none().then_some(pane)
+    }
+
+    /// Where to point `tmux switch-client -t`: the pane id, which stays
+    /// the same when windows are renumbered or moved
+    pub fn target(&self) -> String {
+        self.pane_id.clone()
+    }
+
+    /// Set `@boop_state` on the pane and its window, e.g. to "approval", so
+    /// `#{@boop_state}` can highlight windows that need the user
+    pub fn set_state(&self, state: SessionState) {
+        let value = state_name(state);
+        self.tmux(&["set-option", "-p", "-t", &self.pane_id, STATE_OPTION, value]);
+        self.tmux(&["set-option", "-w", "-t", &self.pane_id, STATE_OPTION, value]);
+    }
+
+    /// Remove `@boop_state` once the session is over, leaving the window's
+    /// to any other session running there. Waits a moment for it to happen,
+    /// since the process is about to exit.
+    pub fn clear_state(&self) {
+        let _ = WORKER.send(Job::Clear(self.pane_id.clone()));
+        let (done, flushed) = mpsc::channel();
+        if WORKER.send(Job::Flush(done)).is_ok() {
+            let _ = flushed.recv_timeout(FLUSH_TIMEOUT);
+        }
+    }
+
+    /// Flash `message` in the status line of clients showing this pane,
+    /// followed by where the pane is now, e.g. "(work:3.1)"
+    pub fn display(&self, message: &str) {
+        // '#' starts a format in display-message
+        let message = format!("{} (#S:#I.#P)", message.replace('#', "##"));
+        self.tmux(&["display-message", "-t", &self.pane_id, &message]);
+    }
+
+    // Best effort: the status line is a nicety, never worth failing over
+    fn tmux(&self, args: &[&str]) {
+        let _ = WORKER.send(Job::Run(args.iter().map(|arg| arg.to_string()).collect()));
+    }
+}
+
+/// Run tmux and wait for it; its output if it succeeded
+fn run<S: AsRef<std::ffi::OsStr>>(args: &[S]) -> Option<String> {
+    let output = Command::new("tmux").args(args).stdin(Stdio::null()).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn clear(pane_id: &str) {
+    run(&["set-option", "-p", "-u", "-t", pane_id, STATE_OPTION]);
+
+    // Another session in the same window keeps it marked with its own state
+    let panes = run(&["list-panes", "-t", pane_id, "-F", "#{pane_id}"]).unwrap_or_default();
+    let other = panes.lines().filter(|pane| *pane != pane_id).find_map(|pane| {
+        run(&["show-options", "-p", "-q", "-v", "-t", pane, STATE_OPTION])
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    });
+    match other {
+        Some(value) => run(&["set-option", "-w", "-t", pane_id, STATE_OPTION, &value]),
+        None => run(&["set-option", "-w", "-u", "-t", pane_id, STATE_OPTION]),
+    };
+}
+
+fn state_name(state: SessionState) -> &'static str {
+    match state {
+        SessionState::Working => "working",
+        SessionState::AwaitingApproval => "approval",
+        SessionState::Idle => "idle",
+        SessionState::Completed => "completed",
+        SessionState::Error => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pane() {
+        let pane = TmuxPane::parse("my agents\t3\t1\t%12\n").unwrap();
+        assert_eq!(pane.session, "my agents");
+        assert_eq!(pane.pane_id, "%12");
+        assert_eq!(pane.target(), "%12");
+
+        assert!(TmuxPane::parse("main\t3\n").is_none());
+        assert!(TmuxPane::parse("main\t3\t1\t%12\textra").is_none());
+    }
+}
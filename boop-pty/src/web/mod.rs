@@ -0,0 +1,322 @@
+//! Optional web UI for `boop-daemon`: lists sessions, shows each one's
+//! screen live and answers permission dialogs, from any browser that has
+//! the token
+
+mod screen;
+mod websocket;
+
+use crate::daemon::{Connections, Registry};
+use crate::detector::{ApprovalRequest, SessionState};
+use crate::error::{BoopError, Result};
+use crate::ipc::Command;
+use crate::tmux::TmuxPane;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+// Keys for one answer; anything bigger is not a button press
+const MAX_BODY: u64 = 4096;
+
+/// A session as the page sees it
+#[derive(Serialize)]
+struct SessionView<'a> {
+    id: &'a str,
+    tool: &'a str,
+    project_name: &'a str,
+    state: &'a str,
+    details: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    approval: Option<&'a ApprovalRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tmux_target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_ms: Option<u64>,
+    updated_secs_ago: u64,
+}
+
+#[derive(Deserialize)]
+struct Respond {
+    keys: String,
+}
+
+pub struct WebServer {
+    server: Server,
+    token: String,
+    registry: Arc<Mutex<Registry>>,
+    connections: Connections,
+}
+
+impl WebServer {
+    /// Listen on `addr`, e.g. "127.0.0.1:7483". Every request but the page
+    /// itself needs `token`, as a bearer token or a `token` query parameter.
+    pub fn bind(addr: &str, token: &str, registry: Arc<Mutex<Registry>>, connections: Connections) -> Result<Self> {
+        if token.is_empty() {
+            return Err(BoopError::Config("the web UI needs a token".to_string()));
+        }
+        let server = Server::http(addr).map_err(|e| BoopError::Ipc(format!("Cannot listen on {}: {}", addr, e)))?;
+        Ok(Self {
+            server,
+            token: token.to_string(),
+            registry,
+            connections,
+        })
+    }
+
+    /// The address actually bound, e.g. with port 0 replaced
+    pub fn addr(&self) -> String {
+        self.server.server_addr().to_string()
+    }
+
+    /// Handle requests until the server fails, each on a thread of its own
+    /// since screens stay open
+    pub fn serve(self) {
+        let web = Arc::new(self);
+        for request in web.server.incoming_requests() {
+            let web = web.clone();
+            thread::spawn(move || web.handle(request));
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        if request.method() == &Method::Get && path == "/" {
+            let page = Response::from_string(INDEX_HTML).with_header(header("Content-Type", "text/html; charset=utf-8"));
+            let _ = request.respond(page);
+            return;
+        }
+        if !self.authorized(&request, query) {
+            let _ = request.respond(error(401, "missing or wrong token"));
+            return;
+        }
+
+        let response = match (request.method(), segments.as_slice()) {
+            (Method::Get, ["api", "sessions"]) => self.sessions(),
+            (Method::Get, ["api", "sessions", id, "screen"]) => {
+                let id = id.to_string();
+                return self.stream_screen(request, &id);
+            }
+            (Method::Post, ["api", "sessions", id, "respond"]) => {
+                let id = id.to_string();
+                let mut body = String::new();
+                let read = request.as_reader().take(MAX_BODY).read_to_string(&mut body);
+                match read.ok().and_then(|_| serde_json::from_str::<Respond>(&body).ok()) {
+                    Some(Respond { keys }) => self.respond(id, keys),
+                    None => error(400, "expected {\"keys\": \"...\"}"),
+                }
+            }
+            (Method::Post, ["api", "sessions", id, "interrupt"]) => self.command(Command::Interrupt {
+                session_id: id.to_string(),
+            }),
+            _ => error(404, "not found"),
+        };
+        let _ = request.respond(response);
+    }
+
+    fn authorized(&self, request: &Request, query: &str) -> bool {
+        let bearer = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .and_then(|header| header.value.as_str().strip_prefix("Bearer "));
+        let param = query.split('&').find_map(|pair| pair.strip_prefix("token="));
+        bearer.or(param).is_some_and(|token| same(token.as_bytes(), self.token.as_bytes()))
+    }
+
+    fn sessions(&self) -> Response<Cursor<Vec<u8>>> {
+        let registry = self.registry.lock().unwrap();
+        let views: Vec<SessionView> = registry
+            .sessions()
+            .iter()
+            .map(|session| SessionView {
+                id: &session.id,
+                tool: &session.tool,
+                project_name: &session.project_name,
+                state: session.state.as_str(),
+                details: &session.details,
+                approval: session.approval.as_ref(),
+                tmux_target: session.tmux.as_ref().map(TmuxPane::target),
+                response_ms: session.response_ms,
+                updated_secs_ago: session.last_update.elapsed().as_secs(),
+            })
+            .collect();
+        json(200, &views)
+    }
+
+    /// Only ever type one of the answers the dialog on screen offers, so
+    /// the token is no way to run arbitrary input
+    fn respond(&self, session_id: String, keys: String) -> Response<Cursor<Vec<u8>>> {
+        let offered = {
+            let registry = self.registry.lock().unwrap();
+            registry.get(&session_id).is_some_and(|session| {
+                session.state == SessionState::AwaitingApproval
+                    && session.approval.as_ref().is_some_and(|approval| approval.keys.contains(&keys))
+            })
+        };
+        if !offered {
+            return error(409, "not an answer to a pending permission dialog");
+        }
+        self.command(Command::Respond { session_id, keys })
+    }
+
+    fn command(&self, command: Command) -> Response<Cursor<Vec<u8>>> {
+        match self.connections.send(&command) {
+            Ok(()) => json(200, &serde_json::json!({ "sent": true })),
+            Err(e) => error(409, &e.to_string()),
+        }
+    }
+
+    fn stream_screen(&self, request: Request, session_id: &str) {
+        let key = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+            .map(|header| header.value.to_string());
+        let Some(key) = key else {
+            let _ = request.respond(error(400, "expected a WebSocket upgrade"));
+            return;
+        };
+
+        let response = Response::empty(101).with_header(header("Sec-WebSocket-Accept", &websocket::accept_key(&key)));
+        let mut socket = request.upgrade("websocket", response);
+        screen::stream(session_id, &mut socket);
+    }
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn json(status: u16, value: &impl Serialize) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(serde_json::to_string(value).unwrap())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Cache-Control", "no-store"))
+}
+
+fn error(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+/// Compare without returning early, so response times don't give away how
+/// much of a guessed token was right
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::Message;
+
+    #[test]
+    fn test_token_and_routes() {
+        let registry = Arc::new(Mutex::new(Registry::new()));
+        registry.lock().unwrap().start("abc", "claude", "boop", 10, None);
+        registry
+            .lock()
+            .unwrap()
+            .update("abc", SessionState::AwaitingApproval, "Bash: ls", None);
+        let web = WebServer::bind("127.0.0.1:0", "secret", registry, Connections::new()).unwrap();
+        let base = format!("http://{}", web.addr());
+        thread::spawn(move || web.serve());
+
+        let status = |result: std::result::Result<ureq::Response, ureq::Error>| match result {
+            Ok(response) => response.status(),
+            Err(ureq::Error::Status(code, _)) => code,
+            Err(e) => panic!("{}", e),
+        };
+
+        // The page itself is public, nothing else is
+        assert!(ureq::get(&base).call().unwrap().into_string().unwrap().contains("<title>Boop"));
+        assert_eq!(status(ureq::get(&format!("{}/api/sessions", base)).call()), 401);
+        assert_eq!(status(ureq::get(&format!("{}/api/sessions?token=guess", base)).call()), 401);
+
+        let body = ureq::get(&format!("{}/api/sessions", base))
+            .set("Authorization", "Bearer secret")
+            .call()
+            .unwrap()
+            .into_string()
+            .unwrap();
+        let sessions: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(sessions[0]["id"], "abc");
+        assert_eq!(sessions[0]["state"], "AWAITING_APPROVAL");
+        assert_eq!(sessions[0]["details"], "Bash: ls");
+
+        // No boop-pty is connected to answer
+        let respond = format!("{}/api/sessions/abc/respond?token=secret", base);
+        assert_eq!(status(ureq::post(&respond).send_string("{\"keys\": \"1\"}")), 409);
+        assert_eq!(status(ureq::post(&respond).send_string("yes")), 400);
+        assert!(!same(b"secret", b"secreT"));
+    }
+
+    #[test]
+    fn test_respond_only_answers_the_dialog() {
+        let registry = Arc::new(Mutex::new(Registry::new()));
+        let request = ApprovalRequest {
+            tool: Some("Bash".to_string()),
+            command: Some("ls".to_string()),
+            choices: vec!["Yes".to_string(), "No".to_string()],
+            keys: vec!["1".to_string(), "2".to_string()],
+            ..Default::default()
+        };
+        registry.lock().unwrap().start("abc", "claude", "boop", 10, None);
+        registry.lock().unwrap().start("idle", "claude", "boop", 11, None);
+        registry
+            .lock()
+            .unwrap()
+            .apply(Message::approval("abc", "Bash: ls", None, request));
+        registry
+            .lock()
+            .unwrap()
+            .update("idle", SessionState::Idle, "", None);
+
+        // Stand in for the sessions' boop-pty, to see what gets typed
+        let connections = Connections::new();
+        let (abc, mut abc_pty) = std::os::unix::net::UnixStream::pair().unwrap();
+        let (idle, mut idle_pty) = std::os::unix::net::UnixStream::pair().unwrap();
+        connections.insert("abc", connections.connection_id(), abc);
+        connections.insert("idle", connections.connection_id(), idle);
+
+        let web = WebServer::bind("127.0.0.1:0", "secret", registry.clone(), connections).unwrap();
+        let base = format!("http://{}", web.addr());
+        thread::spawn(move || web.serve());
+
+        let respond = |id: &str, keys: &str| {
+            let result = ureq::post(&format!("{}/api/sessions/{}/respond?token=secret", base, id))
+                .send_string(&serde_json::json!({ "keys": keys }).to_string());
+            match result {
+                Ok(response) => response.status(),
+                Err(ureq::Error::Status(code, _)) => code,
+                Err(e) => panic!("{}", e),
+            }
+        };
+
+        // Anything but one of the dialog's answers is refused
+        assert_eq!(respond("abc", "rm -rf ~\r"), 409);
+        assert_eq!(respond("abc", "3"), 409);
+        assert_eq!(respond("idle", "1"), 409);
+        assert_eq!(respond("gone", "1"), 409);
+
+        assert_eq!(respond("abc", "2"), 200);
+        let mut line = String::new();
+        std::io::BufRead::read_line(&mut std::io::BufReader::new(&mut abc_pty), &mut line).unwrap();
+        assert!(line.contains("\"keys\":\"2\""), "{}", line);
+
+        // Nor once the dialog has been answered
+        registry
+            .lock()
+            .unwrap()
+            .update("abc", SessionState::Working, "", None);
+        assert_eq!(respond("abc", "1"), 409);
+
+        idle_pty.set_nonblocking(true).unwrap();
+        assert!(idle_pty.read(&mut [0; 64]).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::attach::{self, Frame};
+use crate::web::websocket;
+use serde::Serialize;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Busy output is coalesced into at most this many updates a second
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// What the browser is sent: the screen as text, one string per row
+#[derive(Serialize)]
+struct Update {
+    rows: Vec<String>,
+    cursor: (u16, u16),
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+}
+
+/// Watch the session through its socket and keep `socket`, a WebSocket,
+/// showing its screen until either side goes away
+pub fn stream(session_id: &str, socket: &mut impl Write) {
+    let session = UnixStream::connect(attach::socket_path(session_id))
+        .and_then(|mut session| Frame::Watch.write_to(&mut session).map(|_| session));
+    let Ok(mut session) = session else {
+        let _ = websocket::close(socket);
+        return;
+    };
+
+    let (frames_tx, frames) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(Some(frame)) = Frame::read_from(&mut session) {
+            if frames_tx.send(frame).is_err() {
+                return;
+            }
+        }
+    });
+
+    // Sized by the session's first frame
+    let mut parser = vt100::Parser::new(24, 80, 0);
+    let mut dirty = false;
+    let mut last_sent = Instant::now();
+    loop {
+        let wait = if dirty {
+            FRAME_INTERVAL.saturating_sub(last_sent.elapsed())
+        } else {
+            KEEPALIVE_INTERVAL.saturating_sub(last_sent.elapsed())
+        };
+
+        let mut exit_code = None;
+        match frames.recv_timeout(wait) {
+            Ok(Frame::Resize { cols, rows }) => {
+                parser.set_size(rows.max(2), cols.max(2));
+                dirty = true;
+            }
+            Ok(Frame::Output(data)) => {
+                parser.process(&data);
+                dirty = true;
+            }
+            Ok(Frame::Exit(code)) => exit_code = Some(code),
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let sent = if dirty && last_sent.elapsed() >= FRAME_INTERVAL || exit_code.is_some() {
+            dirty = false;
+            websocket::send_text(socket, &render(&parser, exit_code))
+        } else if last_sent.elapsed() >= KEEPALIVE_INTERVAL {
+            websocket::ping(socket)
+        } else {
+            continue;
+        };
+        if sent.is_err() || exit_code.is_some() {
+            break;
+        }
+        last_sent = Instant::now();
+    }
+
+    let _ = websocket::close(socket);
+}
+
+fn render(parser: &vt100::Parser, exit_code: Option<i32>) -> String {
+    let screen = parser.screen();
+    let (_, cols) = screen.size();
+    let update = Update {
+        rows: screen.rows(0, cols).map(|row| row.trim_end().to_string()).collect(),
+        cursor: screen.cursor_position(),
+        title: screen.title().to_string(),
+        exit_code,
+    };
+    serde_json::to_string(&update).unwrap()
+}
@@ -0,0 +1,72 @@
+//! Just enough of RFC 6455 to push text to a browser: the handshake and
+//! unmasked server frames
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::io::{self, Write};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const TEXT: u8 = 0x1;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`
+pub fn accept_key(key: &str) -> String {
+    // SHA-1 only because the protocol requires it here
+    STANDARD.encode(Sha1::digest(format!("{}{}", key.trim(), GUID).as_bytes()))
+}
+
+pub fn send_text(stream: &mut impl Write, text: &str) -> io::Result<()> {
+    write_frame(stream, TEXT, text.as_bytes())
+}
+
+/// Keeps idle connections open through proxies, and finds out when the
+/// browser has gone
+pub fn ping(stream: &mut impl Write) -> io::Result<()> {
+    write_frame(stream, PING, &[])
+}
+
+pub fn close(stream: &mut impl Write) -> io::Result<()> {
+    write_frame(stream, CLOSE, &[])
+}
+
+fn write_frame(stream: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    // FIN set: every message is a single frame
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+    stream.write_all(&frame)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_and_frames() {
+        // The example from RFC 6455, section 1.3
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        let mut out = Vec::new();
+        send_text(&mut out, "hi").unwrap();
+        assert_eq!(out, [0x81, 2, b'h', b'i']);
+
+        let mut out = Vec::new();
+        send_text(&mut out, &"x".repeat(300)).unwrap();
+        assert_eq!(out[..4], [0x81, 126, 1, 44]);
+        assert_eq!(out.len(), 304);
+    }
+}